@@ -1,6 +1,6 @@
 //! 实现获取指定玩家的 GCL 和 GPL
 
-use screeps_rust_api::{ScreepsApi, ScreepsError, ScreepsResult};
+use screeps_rust_api::{ScreepsApi, ScreepsResult};
 
 #[tokio::main]
 async fn main() {
@@ -10,28 +10,10 @@ async fn main() {
     println!("user {username}: GCL: {}, GPL: {}", gcl, gpl);
 }
 
-async fn get_gcl_gpl(api: &ScreepsApi, username: &str) -> ScreepsResult<(i32, i32)> {
+async fn get_gcl_gpl(api: &ScreepsApi, username: &str) -> ScreepsResult<(u32, u32)> {
+    // 接口报错时会直接返回 ScreepsError::Api
     let user_info = api.get_user_info_by_name(username).await?;
-    if user_info.base_data.ok.unwrap_or(0) != 1 {
-        return Err(ScreepsError::Api(format!(
-            "获取 {} 信息失败，错误: {}",
-            username,
-            user_info.base_data.error.unwrap()
-        )));
-    }
     let user_info = user_info.user.unwrap();
-    let gcl = calculate_gcl(user_info.gcl);
-    let gpl = calculate_gpl(user_info.power);
 
-    Ok((gcl, gpl))
-}
-
-/// GCL 计算公式
-fn calculate_gcl(gcl: u64) -> i32 {
-    ((gcl as f64) / 1000000.0).powf(1.0 / 2.4).floor() as i32 + 1
-}
-
-/// GPL 计算公式
-fn calculate_gpl(power: u64) -> i32 {
-    ((power as f64) / 1000.0).powf(0.5).floor() as i32
+    Ok((user_info.gcl_level(), user_info.gpl_level()))
 }
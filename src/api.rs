@@ -1,24 +1,50 @@
+use std::collections::HashMap;
+
+use futures_util::{StreamExt, stream};
 use serde::{Serialize, de::DeserializeOwned};
+use serde_json::{Value, json};
 
 use crate::{
-    AllShardData, EncodedRoomTerrainData, MyInfoData, MyNameData, RoomStatusData, RoomTerrainData,
+    ActiveBranch, AllShardData, BranchesData, CodeData, CodeModule, ConsoleData,
+    EncodedRoomTerrainData, MapStatsData, MarketOrdersData, MarketOrdersIndexData, MarketStatsData,
+    MemoryData, MemorySegmentsData, MoneyHistoryData, MoneyHistoryEntry, MyInfoData,
+    MyMarketOrdersData, MyNameData, OkData, RoomStatusData, RoomTerrainData, SetCodeData,
     ShardTimeData, UserAllRoomsData, UserInfoData,
     config::ScreepsConfig,
-    error::ScreepsResult,
+    defense::RoomDefenseReport,
+    error::{ScreepsError, ScreepsResult},
     http_client::*,
+    inventory::{InventoryFailure, RoomInventory, UserInventory},
     model::{RoomObjectsData, TokenData},
+    room_name::{IntoRoomName, RoomName},
+    socket::ScreepsSocket,
+    terrain::TerrainGrid,
+    terrain_cache::{TerrainCache, TerrainPrefetchReport},
+    transport::Transport,
+    utils::decode_gz_value,
 };
 
+/// 内存段 id 的最大值
+pub const MAX_MEMORY_SEGMENT_ID: u8 = 99;
+/// 单个内存段的最大长度，按 JS 字符串长度（UTF-16 编码单元）计算
+pub const MAX_MEMORY_SEGMENT_SIZE: usize = 100 * 1024;
+
 /// Screeps Api
 pub struct ScreepsApi {
     /// http 客户端
     pub http_client: ScreepsHttpClient,
+    /// 地形缓存，配置了 `terrain_cache_dir` 时启用
+    pub terrain_cache: Option<TerrainCache>,
 }
 
 impl ScreepsApi {
     pub fn new(config: ScreepsConfig) -> Self {
-        let http_client = ScreepsHttpClient::new(config);
-        Self { http_client }
+        ScreepsHttpClient::new(config).into()
+    }
+
+    /// 使用指定的传输层创建 api，测试时可以传入 `MockTransport`
+    pub fn with_transport(config: ScreepsConfig, transport: impl Transport + 'static) -> Self {
+        ScreepsHttpClient::with_transport(config, transport).into()
     }
 
     /// 登录获取 token 数据
@@ -26,6 +52,11 @@ impl ScreepsApi {
         self.http_client.auth().await
     }
 
+    /// 使用当前 token 建立 websocket 连接
+    pub async fn connect_socket(&self) -> ScreepsResult<ScreepsSocket> {
+        ScreepsSocket::connect(&self.http_client).await
+    }
+
     /// 请求接口
     pub async fn request<T: Serialize, U: DeserializeOwned>(
         &self,
@@ -64,19 +95,90 @@ impl ScreepsApi {
         self.request(Get, "/user/find", Some(&[("id", id)])).await
     }
 
+    /// 汇总玩家所有房间内建筑、creep 和 power creep 中的资源
+    ///
+    /// 同时进行的请求数由 `ScreepsConfig.batch_concurrency` 控制，请求次数用完时按限速排队等待。
+    /// 单个房间查询失败不会中断汇总，失败的房间放在结果的 `failed` 中。
+    /// 参数：
+    /// - username: 玩家名称
+    /// - shard_filter: 只查询指定 shard，`None` 表示所有 shard
+    pub async fn get_user_inventory(
+        &self,
+        username: &str,
+        shard_filter: Option<&str>,
+    ) -> ScreepsResult<UserInventory> {
+        let user = self
+            .get_user_info_by_name(username)
+            .await?
+            .user
+            .ok_or_else(|| ScreepsError::Decode(format!("no user info for {}", username)))?;
+        let shards = self
+            .get_user_rooms(&user._id)
+            .await?
+            .shards
+            .unwrap_or_default();
+
+        let mut inventory = UserInventory {
+            user_id: user._id,
+            username: user.username,
+            ..Default::default()
+        };
+        let mut targets = Vec::new();
+        for (shard, rooms) in shards {
+            if shard_filter.is_some_and(|filter| filter != shard) {
+                continue;
+            }
+            inventory.shards.entry(shard.clone()).or_default();
+            for room in rooms {
+                match room.parse::<RoomName>() {
+                    Ok(room_name) => targets.push((shard.clone(), room_name)),
+                    Err(error) => inventory.failed.push(InventoryFailure {
+                        shard: shard.clone(),
+                        room,
+                        error,
+                    }),
+                }
+            }
+        }
+
+        let user_id = inventory.user_id.clone();
+        let mut results = stream::iter(targets)
+            .map(|(shard, room)| async move {
+                let result = self.get_room_objects(room, &shard).await;
+                (shard, room, result)
+            })
+            .buffer_unordered(self.http_client.config.batch_concurrency.max(1));
+        while let Some((shard, room, result)) = results.next().await {
+            match result {
+                Ok(data) => {
+                    let objects = data.objects.as_deref().unwrap_or_default();
+                    inventory
+                        .add_room(&shard, RoomInventory::from_objects(room, objects, &user_id));
+                }
+                Err(error) => inventory.failed.push(InventoryFailure {
+                    shard,
+                    room: room.to_string(),
+                    error,
+                }),
+            }
+        }
+        Ok(inventory)
+    }
+
     /// 获取房间对象数据
     /// 参数：
-    /// - room: 房间名称
+    /// - room: 房间名称，`&str` 或 `RoomName`，名称不合法时不会发送请求，直接返回 `ScreepsError::InvalidParam`
     /// - shard: shard 名称
     pub async fn get_room_objects(
         &self,
-        room: &str,
+        room: impl IntoRoomName,
         shard: &str,
     ) -> ScreepsResult<RoomObjectsData> {
+        let room = room.into_room_name()?.to_string();
         self.request(
             Get,
             "/game/room-objects",
-            Some(&[("room", room), ("shard", shard)]),
+            Some(&[("room", room.as_str()), ("shard", shard)]),
         )
         .await
     }
@@ -84,13 +186,14 @@ impl ScreepsApi {
     /// 获取房间地形数据
     pub async fn get_room_terrain(
         &self,
-        room: &str,
+        room: impl IntoRoomName,
         shard: &str,
     ) -> ScreepsResult<RoomTerrainData> {
+        let room = room.into_room_name()?.to_string();
         self.request(
             Get,
             "/game/room-terrain",
-            Some(&[("room", room), ("shard", shard)]),
+            Some(&[("room", room.as_str()), ("shard", shard)]),
         )
         .await
     }
@@ -98,27 +201,165 @@ impl ScreepsApi {
     /// 获取编码后的房间地形数据
     pub async fn get_room_terrain_encoded(
         &self,
-        room: &str,
+        room: impl IntoRoomName,
         shard: &str,
     ) -> ScreepsResult<EncodedRoomTerrainData> {
+        let room = room.into_room_name()?.to_string();
         self.request(
             Get,
             "/game/room-terrain",
-            Some(&[("room", room), ("shard", shard), ("encoded", "true")]),
+            Some(&[
+                ("room", room.as_str()),
+                ("shard", shard),
+                ("encoded", "true"),
+            ]),
         )
         .await
     }
 
+    /// 获取房间地形并解码为 `TerrainGrid`，启用地形缓存时优先读取缓存，请求成功后写入缓存
+    ///
+    /// 缓存文件损坏时当作没有缓存，重新请求后覆盖
+    pub async fn get_room_terrain_grid(
+        &self,
+        room: impl IntoRoomName,
+        shard: &str,
+    ) -> ScreepsResult<TerrainGrid> {
+        let room = room.into_room_name()?;
+        if let Some(cache) = &self.terrain_cache {
+            match cache.get(shard, room) {
+                Ok(Some(grid)) => return Ok(grid),
+                Ok(None) | Err(ScreepsError::Decode(_)) => {}
+                Err(error) => return Err(error),
+            }
+        }
+        let data = self.get_room_terrain_encoded(room, shard).await?;
+        let terrain = data
+            .terrain
+            .and_then(|terrain| terrain.into_iter().next())
+            .ok_or_else(|| ScreepsError::Decode(format!("no terrain for {}", room)))?;
+        let grid = TerrainGrid::from_encoded(&terrain.terrain)?;
+        if let Some(cache) = &self.terrain_cache {
+            cache.insert(shard, room, &grid)?;
+        }
+        Ok(grid)
+    }
+
+    /// 批量预取地形到缓存，已缓存的房间不会发送请求
+    ///
+    /// 只使用 `RateLimits` 中地形接口当前周期剩余的请求次数，用完后剩余的房间放入
+    /// `TerrainPrefetchReport.pending`，不会等待限速重置。
+    /// 参数：
+    /// - shard: shard 名称
+    /// - rooms: 房间名称列表
+    pub async fn prefetch_shard_terrain<R: IntoRoomName + Clone>(
+        &self,
+        shard: &str,
+        rooms: &[R],
+    ) -> ScreepsResult<TerrainPrefetchReport> {
+        let Some(cache) = &self.terrain_cache else {
+            return Err(ScreepsError::Config(
+                "terrain cache dir is none".to_string(),
+            ));
+        };
+        let rooms = rooms
+            .iter()
+            .cloned()
+            .map(IntoRoomName::into_room_name)
+            .collect::<ScreepsResult<Vec<_>>>()?;
+
+        let mut report = TerrainPrefetchReport::default();
+        for room in rooms {
+            // 缓存文件损坏时当作没有缓存，重新请求
+            match cache.get(shard, room) {
+                Ok(Some(_)) => {
+                    report.cached.push(room);
+                    continue;
+                }
+                Ok(None) | Err(ScreepsError::Decode(_)) => {}
+                Err(error) => return Err(error),
+            }
+            let available = self.http_client.rate_limits.lock().unwrap().available(
+                &Get,
+                "/game/room-terrain",
+                (self.http_client.clock)(),
+            );
+            if available <= 0 {
+                report.pending.push(room);
+                continue;
+            }
+            match self.get_room_terrain_grid(room, shard).await {
+                Ok(_) => report.fetched.push(room),
+                Err(ScreepsError::RateLimited { .. }) => report.pending.push(room),
+                Err(error) => report.failed.push((room, error)),
+            }
+        }
+        Ok(report)
+    }
+
     /// 获取房间状态数据
-    pub async fn get_room_status(&self, room: &str, shard: &str) -> ScreepsResult<RoomStatusData> {
+    pub async fn get_room_status(
+        &self,
+        room: impl IntoRoomName,
+        shard: &str,
+    ) -> ScreepsResult<RoomStatusData> {
+        let room = room.into_room_name()?.to_string();
         self.request(
             Get,
             "/game/room-status",
-            Some(&[("room", room), ("shard", shard)]),
+            Some(&[("room", room.as_str()), ("shard", shard)]),
+        )
+        .await
+    }
+
+    /// 批量获取房间的地图统计数据
+    /// 参数：
+    /// - rooms: 房间名称列表
+    /// - shard: shard 名称
+    /// - stat_name: 统计项，如 `owner0`、`minerals0`、`claim0`
+    pub async fn get_map_stats<R: IntoRoomName + Clone>(
+        &self,
+        rooms: &[R],
+        shard: &str,
+        stat_name: &str,
+    ) -> ScreepsResult<MapStatsData> {
+        let rooms = rooms
+            .iter()
+            .cloned()
+            .map(IntoRoomName::into_room_name)
+            .collect::<ScreepsResult<Vec<_>>>()?;
+        self.request(
+            Post,
+            "/game/map-stats",
+            Some(json!({
+                "rooms": rooms,
+                "shard": shard,
+                "statName": stat_name,
+            })),
         )
         .await
     }
 
+    /// 评估房间的防御，汇总 tower、rampart、wall、安全模式和 creep 的战斗力
+    pub async fn assess_room_defense(
+        &self,
+        room: impl IntoRoomName,
+        shard: &str,
+    ) -> ScreepsResult<RoomDefenseReport> {
+        let room = room.into_room_name()?;
+        let objects = self.get_room_objects(room, shard).await?;
+        let game_time = self
+            .get_shard_time(shard)
+            .await?
+            .time
+            .ok_or_else(|| ScreepsError::Decode(format!("no game time for {}", shard)))?;
+        Ok(RoomDefenseReport::from_objects(
+            room,
+            objects.objects.as_deref().unwrap_or_default(),
+            game_time,
+        ))
+    }
+
     /// 获取所有shard的信息
     pub async fn get_shards(&self) -> ScreepsResult<AllShardData> {
         self.request::<AnyPayload, AllShardData>(Get, "/game/shards/info", None)
@@ -130,20 +371,1077 @@ impl ScreepsApi {
         self.request(Get, "/game/time", Some(&[("shard", shard)]))
             .await
     }
+
+    /// 获取指定分支的代码
+    pub async fn get_code(&self, branch: &str) -> ScreepsResult<CodeData> {
+        self.request(Get, "/user/code", Some(&[("branch", branch)]))
+            .await
+    }
+
+    /// 上传代码到指定分支，会覆盖该分支的全部模块
+    /// 参数：
+    /// - branch: 分支名
+    /// - modules: 模块名到模块内容的映射，wasm 等二进制模块使用 `CodeModule::Binary`
+    pub async fn set_code(
+        &self,
+        branch: &str,
+        modules: &HashMap<String, CodeModule>,
+    ) -> ScreepsResult<SetCodeData> {
+        self.request(
+            Post,
+            "/user/code",
+            Some(json!({
+                "branch": branch,
+                "modules": modules,
+            })),
+        )
+        .await
+    }
+
+    /// 获取所有代码分支
+    pub async fn get_branches(&self) -> ScreepsResult<BranchesData> {
+        self.request::<AnyPayload, BranchesData>(Get, "/user/branches", None)
+            .await
+    }
+
+    /// 克隆分支
+    /// 参数：
+    /// - branch: 被克隆的分支名
+    /// - new_name: 新分支名
+    pub async fn clone_branch(&self, branch: &str, new_name: &str) -> ScreepsResult<OkData> {
+        self.request(
+            Post,
+            "/user/clone-branch",
+            Some(json!({
+                "branch": branch,
+                "newName": new_name,
+            })),
+        )
+        .await
+    }
+
+    /// 删除分支
+    pub async fn delete_branch(&self, branch: &str) -> ScreepsResult<OkData> {
+        self.request(
+            Post,
+            "/user/delete-branch",
+            Some(json!({ "branch": branch })),
+        )
+        .await
+    }
+
+    /// 设置激活分支
+    /// 参数：
+    /// - branch: 分支名
+    /// - active: 在世界服务器还是模拟器中激活
+    pub async fn set_active_branch(
+        &self,
+        branch: &str,
+        active: ActiveBranch,
+    ) -> ScreepsResult<OkData> {
+        self.request(
+            Post,
+            "/user/set-active-branch",
+            Some(json!({
+                "branch": branch,
+                "activeName": active.as_str(),
+            })),
+        )
+        .await
+    }
+
+    /// 获取内存数据，返回解压后的 json
+    /// 参数：
+    /// - path: 内存路径，如 `stats.cpu`，传空字符串表示整个 Memory
+    /// - shard: shard 名称
+    pub async fn get_memory(&self, path: &str, shard: &str) -> ScreepsResult<MemoryData> {
+        self.get_memory_as(path, shard).await
+    }
+
+    /// 获取内存数据，并解压反序列化为指定类型
+    pub async fn get_memory_as<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        shard: &str,
+    ) -> ScreepsResult<MemoryData<T>> {
+        let memory: MemoryData = self
+            .request(
+                Get,
+                "/user/memory",
+                Some(&[("path", path), ("shard", shard)]),
+            )
+            .await?;
+        let data = match memory.data {
+            Some(Value::Null) | None => None,
+            Some(data) => Some(decode_gz_value(data)?),
+        };
+        Ok(MemoryData {
+            base_data: memory.base_data,
+            data,
+        })
+    }
+
+    /// 设置内存数据
+    /// 参数：
+    /// - path: 内存路径，传空字符串表示整个 Memory
+    /// - value: 要写入的值
+    /// - shard: shard 名称
+    pub async fn set_memory<T: Serialize>(
+        &self,
+        path: &str,
+        value: &T,
+        shard: &str,
+    ) -> ScreepsResult<OkData> {
+        self.request(
+            Post,
+            "/user/memory",
+            Some(json!({
+                "path": path,
+                "value": value,
+                "shard": shard,
+            })),
+        )
+        .await
+    }
+
+    /// 批量获取内存段数据
+    /// 参数：
+    /// - ids: 内存段 id 列表，范围 0-99
+    /// - shard: shard 名称
+    pub async fn get_memory_segments(
+        &self,
+        ids: &[u8],
+        shard: &str,
+    ) -> ScreepsResult<MemorySegmentsData> {
+        if ids.is_empty() {
+            return Err(ScreepsError::InvalidParam(
+                "memory segment ids is empty".to_string(),
+            ));
+        }
+        for id in ids {
+            check_memory_segment_id(*id)?;
+        }
+        let segment = ids
+            .iter()
+            .map(|id| id.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        let segments: MemoryData = self
+            .request(
+                Get,
+                "/user/memory-segment",
+                Some(&[("segment", segment.as_str()), ("shard", shard)]),
+            )
+            .await?;
+        // 只请求一个内存段时服务器直接返回字符串
+        let data = match segments.data {
+            None | Some(Value::Null) => None,
+            Some(Value::Array(list)) => Some(serde_json::from_value(Value::Array(list))?),
+            Some(data) => Some(vec![serde_json::from_value(data)?]),
+        };
+        Ok(MemorySegmentsData {
+            base_data: segments.base_data,
+            data,
+        })
+    }
+
+    /// 设置内存段数据
+    /// 参数：
+    /// - id: 内存段 id，范围 0-99
+    /// - data: 内存段内容，最多 100 * 1024 个字符，按 JS 字符串长度（UTF-16 编码单元）计算
+    /// - shard: shard 名称
+    pub async fn set_memory_segment(
+        &self,
+        id: u8,
+        data: &str,
+        shard: &str,
+    ) -> ScreepsResult<OkData> {
+        check_memory_segment_id(id)?;
+        let len = data.encode_utf16().count();
+        if len > MAX_MEMORY_SEGMENT_SIZE {
+            return Err(ScreepsError::InvalidParam(format!(
+                "memory segment {} is {} characters, exceeds {} characters",
+                id, len, MAX_MEMORY_SEGMENT_SIZE
+            )));
+        }
+        self.request(
+            Post,
+            "/user/memory-segment",
+            Some(json!({
+                "segment": id,
+                "data": data,
+                "shard": shard,
+            })),
+        )
+        .await
+    }
+
+    /// 在指定 shard 执行控制台命令
+    /// 参数：
+    /// - expression: 要执行的表达式，如 `Game.cpu.bucket`
+    /// - shard: shard 名称
+    pub async fn send_console_command(
+        &self,
+        expression: &str,
+        shard: &str,
+    ) -> ScreepsResult<ConsoleData> {
+        self.request(
+            Post,
+            "/user/console",
+            Some(json!({
+                "expression": expression,
+                "shard": shard,
+            })),
+        )
+        .await
+    }
+
+    /// 获取市场订单索引，即每种资源的订单数量
+    pub async fn get_market_orders_index(
+        &self,
+        shard: &str,
+    ) -> ScreepsResult<MarketOrdersIndexData> {
+        self.request(Get, "/game/market/orders-index", Some(&[("shard", shard)]))
+            .await
+    }
+
+    /// 获取指定资源的所有市场订单
+    /// 参数：
+    /// - resource_type: 资源类型，如 `energy`、`XUHO2`
+    /// - shard: shard 名称
+    pub async fn get_market_orders(
+        &self,
+        resource_type: &str,
+        shard: &str,
+    ) -> ScreepsResult<MarketOrdersData> {
+        self.request(
+            Get,
+            "/game/market/orders",
+            Some(&[("resourceType", resource_type), ("shard", shard)]),
+        )
+        .await
+    }
+
+    /// 获取自己在所有 shard 的市场订单
+    pub async fn get_my_market_orders(&self) -> ScreepsResult<MyMarketOrdersData> {
+        self.request::<AnyPayload, MyMarketOrdersData>(Get, "/game/market/my-orders", None)
+            .await
+    }
+
+    /// 获取指定资源最近的每日市场统计
+    pub async fn get_market_stats(
+        &self,
+        resource_type: &str,
+        shard: &str,
+    ) -> ScreepsResult<MarketStatsData> {
+        self.request(
+            Get,
+            "/game/market/stats",
+            Some(&[("resourceType", resource_type), ("shard", shard)]),
+        )
+        .await
+    }
+
+    /// 获取指定页的 credits 变动记录
+    /// 参数：
+    /// - page: 页码，从 0 开始
+    pub async fn get_money_history(&self, page: u32) -> ScreepsResult<MoneyHistoryData> {
+        self.request(
+            Get,
+            "/user/money-history",
+            Some(&[("page", page.to_string())]),
+        )
+        .await
+    }
+
+    /// 从第 0 页开始逐页遍历 credits 变动记录
+    pub fn money_history_pages(&self) -> MoneyHistoryPages<'_> {
+        MoneyHistoryPages {
+            api: self,
+            page: 0,
+            done: false,
+        }
+    }
+}
+
+/// credits 变动记录的分页迭代器
+pub struct MoneyHistoryPages<'a> {
+    api: &'a ScreepsApi,
+    page: u32,
+    done: bool,
+}
+
+impl MoneyHistoryPages<'_> {
+    /// 获取下一页记录，没有更多记录时返回 `None`，请求失败后迭代结束
+    pub async fn next_page(&mut self) -> Option<ScreepsResult<Vec<MoneyHistoryEntry>>> {
+        if self.done {
+            return None;
+        }
+        let data = match self.api.get_money_history(self.page).await {
+            Ok(data) => data,
+            Err(e) => {
+                self.done = true;
+                return Some(Err(e));
+            }
+        };
+        self.page += 1;
+        let list = data.list.unwrap_or_default();
+        if !data.has_more.unwrap_or(false) || list.is_empty() {
+            self.done = true;
+        }
+        if list.is_empty() {
+            return None;
+        }
+        Some(Ok(list))
+    }
+
+    /// 下一次请求的页码
+    pub fn page(&self) -> u32 {
+        self.page
+    }
+}
+
+/// 检查内存段 id 是否合法
+fn check_memory_segment_id(id: u8) -> ScreepsResult<()> {
+    if id > MAX_MEMORY_SEGMENT_ID {
+        return Err(ScreepsError::InvalidParam(format!(
+            "memory segment id {} out of range 0-{}",
+            id, MAX_MEMORY_SEGMENT_ID
+        )));
+    }
+    Ok(())
+}
+
+impl From<ScreepsHttpClient> for ScreepsApi {
+    fn from(http_client: ScreepsHttpClient) -> Self {
+        let terrain_cache = http_client
+            .config
+            .terrain_cache_dir
+            .clone()
+            .map(TerrainCache::new);
+        Self {
+            http_client,
+            terrain_cache,
+        }
+    }
 }
 
 impl Default for ScreepsApi {
     /// 默认实现只能调用无 token 要求的接口
     fn default() -> Self {
-        let http_client = ScreepsHttpClient::new(ScreepsConfig::default());
-        Self { http_client }
+        ScreepsHttpClient::new(ScreepsConfig::default()).into()
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::{io::Write, sync::Arc};
+
+    use base64::{Engine, prelude::BASE64_STANDARD};
+    use flate2::{Compression, write::GzEncoder};
+
     use super::*;
-    use crate::screeps_api_from_env;
+    use crate::{
+        MockResponse, MockTransport, ResourceType, RoomObject, Terrain, TerrainGrid,
+        screeps_api_from_env,
+    };
+
+    fn mock_api() -> (ScreepsApi, Arc<MockTransport>) {
+        let transport = Arc::new(MockTransport::new());
+        let mut config = ScreepsConfig::default();
+        config.with_token("token".to_string());
+        (
+            ScreepsApi::with_transport(config, transport.clone()),
+            transport,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_mock_user_endpoints() {
+        let (api, transport) = mock_api();
+        transport
+            .on(
+                Get,
+                "/user/find",
+                MockResponse::json(&json!({
+                    "ok": 1,
+                    "user": { "_id": "u1", "username": "keqing", "gcl": 100, "power": 0 }
+                })),
+            )
+            .on(
+                Get,
+                "/user/rooms",
+                MockResponse::json(&json!({
+                    "ok": 1,
+                    "shards": { "shard3": ["E13S13"] },
+                    "reservations": {}
+                })),
+            )
+            .on(
+                Get,
+                "/user/name",
+                MockResponse::json(&json!({ "ok": 1, "username": "keqing" })),
+            );
+
+        let user = api.get_user_info_by_name("keqing").await.unwrap();
+        assert_eq!(user.user.unwrap()._id, "u1");
+        let rooms = api.get_user_rooms("u1").await.unwrap();
+        assert_eq!(rooms.shards.unwrap()["shard3"], vec!["E13S13"]);
+        let name = api.get_my_name().await.unwrap();
+        assert_eq!(name.username.as_deref(), Some("keqing"));
+
+        let requests = transport.requests();
+        assert_eq!(requests[0].query("username").as_deref(), Some("keqing"));
+        assert_eq!(requests[1].query("id").as_deref(), Some("u1"));
+    }
+
+    #[tokio::test]
+    async fn test_mock_user_not_found() {
+        let (api, transport) = mock_api();
+        transport.on(
+            Get,
+            "/user/find",
+            MockResponse::json(&json!({ "error": "user not found" })),
+        );
+        match api.get_user_info_by_id("nobody").await {
+            Err(ScreepsError::Api(error)) => {
+                assert_eq!(error.kind, crate::ApiErrorKind::UserNotFound)
+            }
+            result => panic!("unexpected result {:?}", result),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mock_room_endpoints() {
+        let (api, transport) = mock_api();
+        transport
+            .on(
+                Get,
+                "/game/room-objects",
+                MockResponse::json(&json!({
+                    "ok": 1,
+                    "objects": [{
+                        "_id": "s1", "type": "source", "room": "E13S13", "x": 1, "y": 2,
+                        "energy": 3000, "energyCapacity": 3000, "ticksToRegeneration": 300,
+                        "invaderHarvested": 0
+                    }],
+                    "users": {}
+                })),
+            )
+            .on(
+                Get,
+                "/game/room-terrain",
+                MockResponse::json(&json!({
+                    "ok": 1,
+                    "terrain": [{ "_id": "t1", "room": "E13S13", "terrain": "0".repeat(2500), "type": "terrain" }]
+                })),
+            )
+            .on(
+                Get,
+                "/game/room-status",
+                MockResponse::json(&json!({
+                    "ok": 1,
+                    "rooms": { "_id": "E13S13", "status": "normal" }
+                })),
+            )
+            .on(
+                Get,
+                "/game/time",
+                MockResponse::json(&json!({ "ok": 1, "time": 123 })),
+            );
+
+        let objects = api.get_room_objects("E13S13", "shard3").await.unwrap();
+        assert!(matches!(objects.objects.unwrap()[0], RoomObject::Source(_)));
+        let terrain = api
+            .get_room_terrain_encoded("E13S13", "shard3")
+            .await
+            .unwrap();
+        assert_eq!(terrain.terrain.unwrap()[0].terrain.len(), 2500);
+        let grid = api.get_room_terrain_grid("E13S13", "shard3").await.unwrap();
+        assert_eq!(grid.count(Terrain::Plain), 2500);
+        let status = api.get_room_status("E13S13", "shard3").await.unwrap();
+        assert_eq!(status.rooms.unwrap().status, "normal");
+        let time = api.get_shard_time("shard3").await.unwrap();
+        assert_eq!(time.time, Some(123));
+
+        let terrain_request = &transport.requests_to("/game/room-terrain")[0];
+        assert_eq!(terrain_request.query("encoded").as_deref(), Some("true"));
+        assert_eq!(terrain_request.query("shard").as_deref(), Some("shard3"));
+    }
+
+    #[tokio::test]
+    async fn test_mock_assess_room_defense() {
+        let (api, transport) = mock_api();
+        transport
+            .on(
+                Get,
+                "/game/room-objects",
+                MockResponse::json(&json!({
+                    "ok": 1,
+                    "objects": [
+                        { "_id": "ctrl", "type": "controller", "room": "E13S13", "x": 20, "y": 20,
+                          "level": 3, "user": "owner", "safeModeAvailable": 1 },
+                        { "_id": "t1", "type": "tower", "room": "E13S13", "x": 25, "y": 25,
+                          "hits": 3000, "hitsMax": 3000, "notifyWhenAttacked": true, "user": "owner",
+                          "store": { "energy": 500 }, "storeCapacityResource": { "energy": 1000 },
+                          "actionLog": {} }
+                    ],
+                    "users": {}
+                })),
+            )
+            .on(
+                Get,
+                "/game/time",
+                MockResponse::json(&json!({ "ok": 1, "time": 100 })),
+            );
+
+        let report = api.assess_room_defense("E13S13", "shard3").await.unwrap();
+        assert_eq!(report.room, RoomName::new("E13S13").unwrap());
+        assert_eq!(report.game_time, 100);
+        assert_eq!(report.controller_level, Some(3));
+        assert!(report.safe_mode.can_activate());
+        assert_eq!(report.tower_damage_at(25, 30), 600.0);
+        assert_eq!(report.hostiles.count, 0);
+    }
+
+    #[tokio::test]
+    async fn test_mock_get_user_inventory() {
+        let (api, transport) = mock_api();
+        transport
+            .on(
+                Get,
+                "/user/find",
+                MockResponse::json(&json!({
+                    "ok": 1,
+                    "user": { "_id": "u1", "username": "keqing", "gcl": 0, "power": 0 }
+                })),
+            )
+            .on(
+                Get,
+                "/user/rooms",
+                MockResponse::json(&json!({
+                    "ok": 1,
+                    "shards": { "shard3": ["E13S13", "E14S13"], "shard2": ["W1N1"] }
+                })),
+            )
+            .on(
+                Get,
+                "/game/room-objects",
+                MockResponse::json(&json!({
+                    "ok": 1,
+                    "objects": [{
+                        "_id": "l1", "type": "lab", "room": "E13S13", "x": 1, "y": 2,
+                        "hits": 500, "hitsMax": 500, "user": "u1", "notifyWhenAttacked": true,
+                        "store": { "energy": 2000, "XUH2O": 3000 },
+                        "storeCapacityResource": { "energy": 2000 }
+                    }],
+                    "users": {}
+                })),
+            );
+
+        let inventory = api
+            .get_user_inventory("keqing", Some("shard3"))
+            .await
+            .unwrap();
+        assert!(inventory.is_complete());
+        assert_eq!(inventory.user_id, "u1");
+        assert_eq!(inventory.shards.len(), 1);
+        let shard = &inventory.shards["shard3"];
+        assert_eq!(shard.rooms.len(), 2);
+        assert_eq!(shard.total.get(ResourceType::CatalyzedUtriumAcid), 6000);
+        assert_eq!(inventory.total.get(ResourceType::Energy), 4000);
+        assert_eq!(shard.by_type()["lab"].get(ResourceType::Energy), 4000);
+        assert_eq!(transport.requests_to("/game/room-objects").len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_mock_get_user_inventory_partial_failure() {
+        let (api, transport) = mock_api();
+        transport
+            .on(
+                Get,
+                "/user/find",
+                MockResponse::json(&json!({
+                    "ok": 1,
+                    "user": { "_id": "u1", "username": "keqing", "gcl": 0, "power": 0 }
+                })),
+            )
+            .on(
+                Get,
+                "/user/rooms",
+                MockResponse::json(&json!({
+                    "ok": 1,
+                    "shards": { "shard3": ["E13S13", "sim"] }
+                })),
+            )
+            .on(
+                Get,
+                "/game/room-objects",
+                MockResponse::json(&json!({ "ok": 0, "error": "invalid room" })),
+            );
+
+        let inventory = api.get_user_inventory("keqing", None).await.unwrap();
+        assert!(!inventory.is_complete());
+        assert_eq!(inventory.failed.len(), 2);
+        assert!(inventory.shards["shard3"].rooms.is_empty());
+        let invalid = inventory
+            .failed
+            .iter()
+            .find(|failure| failure.room == "sim")
+            .unwrap();
+        assert!(matches!(invalid.error, ScreepsError::InvalidParam(_)));
+        // 不合法的房间名不会发送请求
+        assert_eq!(transport.requests_to("/game/room-objects").len(), 1);
+    }
+
+    fn terrain_response(room: &str) -> MockResponse {
+        MockResponse::json(&json!({
+            "ok": 1,
+            "terrain": [{ "_id": "t1", "room": room, "terrain": "1".repeat(2500), "type": "terrain" }]
+        }))
+    }
+
+    #[tokio::test]
+    async fn test_mock_terrain_cache() {
+        let dir = std::env::temp_dir().join(format!("api-terrain-cache-{}", std::process::id()));
+        let transport = Arc::new(MockTransport::new());
+        transport.on(Get, "/game/room-terrain", terrain_response("E13S13"));
+        let mut config = ScreepsConfig::default();
+        config.with_terrain_cache_dir(dir.clone());
+        let api = ScreepsApi::with_transport(config, transport.clone());
+
+        let grid = api.get_room_terrain_grid("E13S13", "shard3").await.unwrap();
+        assert_eq!(grid.count(Terrain::Wall), 2500);
+        assert_eq!(
+            api.get_room_terrain_grid("E13S13", "shard3").await.unwrap(),
+            grid
+        );
+        assert_eq!(transport.requests().len(), 1);
+
+        // 重启后从文件读取
+        let mut config = ScreepsConfig::default();
+        config.with_terrain_cache_dir(dir.clone());
+        let api = ScreepsApi::with_transport(config, transport.clone());
+        api.get_room_terrain_grid("E13S13", "shard3").await.unwrap();
+        assert_eq!(transport.requests().len(), 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_mock_terrain_cache_broken_file() {
+        let dir = std::env::temp_dir().join(format!("api-terrain-broken-{}", std::process::id()));
+        let file = dir.join("shard3").join("E13S13");
+        std::fs::create_dir_all(dir.join("shard3")).unwrap();
+        std::fs::write(&file, b"broken").unwrap();
+        let transport = Arc::new(MockTransport::new());
+        transport.on(Get, "/game/room-terrain", terrain_response("E13S13"));
+        let mut config = ScreepsConfig::default();
+        config.with_terrain_cache_dir(dir.clone());
+        let api = ScreepsApi::with_transport(config, transport.clone());
+
+        // 损坏的缓存文件当作未命中，重新请求并覆盖
+        let grid = api.get_room_terrain_grid("E13S13", "shard3").await.unwrap();
+        assert_eq!(grid.count(Terrain::Wall), 2500);
+        assert_eq!(transport.requests().len(), 1);
+        assert_eq!(
+            TerrainCache::new(&dir)
+                .get("shard3", "E13S13".parse().unwrap())
+                .unwrap(),
+            Some(grid)
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_mock_prefetch_shard_terrain() {
+        let dir = std::env::temp_dir().join(format!("api-terrain-prefetch-{}", std::process::id()));
+        let transport = Arc::new(MockTransport::new());
+        let reset = now_millis() / 1000 + 3600;
+        transport
+            .on(
+                Get,
+                "/game/room-terrain",
+                terrain_response("E1N1").rate_limit(360, 1, reset as u64),
+            )
+            .on(
+                Get,
+                "/game/room-terrain",
+                terrain_response("E1N2").rate_limit(360, 0, reset as u64),
+            );
+        let mut config = ScreepsConfig::default();
+        config.with_terrain_cache_dir(dir.clone());
+        let api = ScreepsApi::with_transport(config, transport.clone());
+        let cached = RoomName::new("E1N0").unwrap();
+        api.terrain_cache
+            .as_ref()
+            .unwrap()
+            .insert("shard3", cached, &TerrainGrid::new())
+            .unwrap();
+
+        let rooms = ["E1N0", "E1N1", "E1N2", "E1N3", "E1N4"];
+        let report = api.prefetch_shard_terrain("shard3", &rooms).await.unwrap();
+        let names = |rooms: &[RoomName]| rooms.iter().map(|r| r.to_string()).collect::<Vec<_>>();
+        assert_eq!(names(&report.cached), ["E1N0"]);
+        assert_eq!(names(&report.fetched), ["E1N1", "E1N2"]);
+        assert_eq!(names(&report.pending), ["E1N3", "E1N4"]);
+        assert!(report.failed.is_empty());
+        assert!(!report.is_complete());
+        assert_eq!(transport.requests().len(), 2);
+
+        let result = ScreepsApi::default()
+            .prefetch_shard_terrain("shard3", &rooms)
+            .await;
+        assert!(matches!(result, Err(ScreepsError::Config(_))));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_mock_prefetch_broken_cache_file() {
+        let dir = std::env::temp_dir().join(format!(
+            "api-terrain-prefetch-broken-{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(dir.join("shard3")).unwrap();
+        std::fs::write(dir.join("shard3").join("E1N1"), b"broken").unwrap();
+        let transport = Arc::new(MockTransport::new());
+        transport.on(Get, "/game/room-terrain", terrain_response("E1N1"));
+        let mut config = ScreepsConfig::default();
+        config.with_terrain_cache_dir(dir.clone());
+        let api = ScreepsApi::with_transport(config, transport.clone());
+
+        let report = api
+            .prefetch_shard_terrain("shard3", &["E1N1"])
+            .await
+            .unwrap();
+        assert!(report.cached.is_empty());
+        assert_eq!(report.fetched, [RoomName::new("E1N1").unwrap()]);
+        assert!(report.is_complete());
+        assert_eq!(transport.requests().len(), 1);
+        assert!(
+            TerrainCache::new(&dir)
+                .get("shard3", RoomName::new("E1N1").unwrap())
+                .unwrap()
+                .is_some()
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_mock_invalid_room_name() {
+        let (api, transport) = mock_api();
+        let room = RoomName::new("e13s13").unwrap();
+        transport.on(
+            Get,
+            "/game/room-status",
+            MockResponse::json(
+                &json!({ "ok": 1, "rooms": { "_id": "E13S13", "status": "normal" } }),
+            ),
+        );
+        api.get_room_status(room, "shard3").await.unwrap();
+        assert_eq!(
+            transport.requests_to("/game/room-status")[0]
+                .query("room")
+                .as_deref(),
+            Some("E13S13")
+        );
+
+        let result = api.get_room_objects("E13", "shard3").await;
+        assert!(matches!(result, Err(ScreepsError::InvalidParam(_))));
+        let result = api
+            .get_map_stats(&["E1N1", "oops"], "shard3", "owner0")
+            .await;
+        assert!(matches!(result, Err(ScreepsError::InvalidParam(_))));
+        assert_eq!(transport.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_mock_code_endpoints() {
+        let (api, transport) = mock_api();
+        transport
+            .on(
+                Get,
+                "/user/code",
+                MockResponse::json(&json!({
+                    "ok": 1,
+                    "branch": "default",
+                    "modules": { "main": "", "bot": { "binary": "AGFzbQ==" } }
+                })),
+            )
+            .on(
+                Post,
+                "/user/code",
+                MockResponse::json(&json!({ "ok": 1, "timestamp": 1 })),
+            )
+            .on(
+                Get,
+                "/user/branches",
+                MockResponse::json(&json!({
+                    "ok": 1,
+                    "list": [{ "_id": "b1", "branch": "default", "activeWorld": true, "activeSim": false }]
+                })),
+            )
+            .on(
+                Post,
+                "/user/set-active-branch",
+                MockResponse::json(&json!({ "ok": 1 })),
+            );
+
+        let code = api.get_code("default").await.unwrap();
+        assert!(code.modules.unwrap()["bot"].is_binary());
+        let modules = HashMap::from([("bot".to_string(), CodeModule::from_bytes(b"\0asm"))]);
+        api.set_code("default", &modules).await.unwrap();
+        let branches = api.get_branches().await.unwrap();
+        assert_eq!(branches.list.unwrap()[0].active_world, Some(true));
+        api.set_active_branch("default", ActiveBranch::Sim)
+            .await
+            .unwrap();
+
+        let set_code = &transport.requests()[1];
+        assert_eq!(
+            set_code.json(),
+            Some(json!({ "branch": "default", "modules": { "bot": { "binary": "AGFzbQ==" } } }))
+        );
+        assert_eq!(
+            transport.requests_to("/user/set-active-branch")[0].json(),
+            Some(json!({ "branch": "default", "activeName": "activeSim" }))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_mock_memory_endpoints() {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(br#"{"cpu":12}"#).unwrap();
+        let data = format!("gz:{}", BASE64_STANDARD.encode(encoder.finish().unwrap()));
+
+        let (api, transport) = mock_api();
+        transport
+            .on(
+                Get,
+                "/user/memory",
+                MockResponse::json(&json!({ "ok": 1, "data": data })),
+            )
+            .on(
+                Get,
+                "/user/memory-segment",
+                MockResponse::json(&json!({ "ok": 1, "data": ["a", null] })),
+            )
+            .on(
+                Get,
+                "/user/memory-segment",
+                MockResponse::json(&json!({ "ok": 1, "data": "only" })),
+            );
+
+        let memory = api.get_memory("stats", "shard3").await.unwrap();
+        assert_eq!(memory.data, Some(json!({ "cpu": 12 })));
+        let segments = api.get_memory_segments(&[1, 2], "shard3").await.unwrap();
+        assert_eq!(segments.data, Some(vec![Some("a".to_string()), None]));
+        let segments = api.get_memory_segments(&[3], "shard3").await.unwrap();
+        assert_eq!(segments.data, Some(vec![Some("only".to_string())]));
+
+        let requests = transport.requests_to("/user/memory-segment");
+        assert_eq!(requests[0].query("segment").as_deref(), Some("1,2"));
+    }
+
+    #[tokio::test]
+    async fn test_mock_send_console_command() {
+        let (api, transport) = mock_api();
+        transport
+            .on(
+                Post,
+                "/user/console",
+                MockResponse::json(&json!({ "ok": 1, "result": { "ok": 1, "n": 1 } })),
+            )
+            .on(
+                Post,
+                "/user/console",
+                MockResponse::json(&json!({ "error": "not authorized" })),
+            );
+
+        let console = api
+            .send_console_command("Game.cpu.bucket", "shard3")
+            .await
+            .unwrap();
+        assert_eq!(console.result.unwrap().ok, Some(1));
+        let requests = transport.requests_to("/user/console");
+        assert_eq!(requests[0].method, Post);
+        assert_eq!(
+            requests[0].json(),
+            Some(json!({ "expression": "Game.cpu.bucket", "shard": "shard3" }))
+        );
+
+        let result = api.send_console_command("1 + 1", "shard0").await;
+        assert!(matches!(result, Err(ScreepsError::Api(_))));
+        assert_eq!(
+            transport.requests_to("/user/console")[1].json(),
+            Some(json!({ "expression": "1 + 1", "shard": "shard0" }))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_mock_map_stats() {
+        let (api, transport) = mock_api();
+        transport.on(
+            Post,
+            "/game/map-stats",
+            MockResponse::json(&json!({
+                "ok": 1,
+                "stats": {
+                    "E13S13": {
+                        "status": "normal",
+                        "own": { "user": "u1", "level": 8 },
+                        "safeMode": false,
+                        "minerals0": { "type": "H", "density": 3 }
+                    }
+                },
+                "users": { "u1": { "_id": "u1", "username": "keqing" } }
+            })),
+        );
+
+        let map_stats = api
+            .get_map_stats(&["E13S13"], "shard3", "owner0")
+            .await
+            .unwrap();
+        let room = &map_stats.stats.unwrap()["E13S13"];
+        assert_eq!(room.own.as_ref().unwrap().level, 8);
+        assert_eq!(room.mineral.as_ref().unwrap().r#type, "H");
+        assert_eq!(map_stats.users.unwrap()["u1"].username, "keqing");
+        assert_eq!(
+            transport.requests_to("/game/map-stats")[0].json(),
+            Some(json!({ "rooms": ["E13S13"], "shard": "shard3", "statName": "owner0" }))
+        );
+
+        // 房间名不合法时不发送请求
+        let result = api
+            .get_map_stats(&["E13S13", "nowhere"], "shard3", "owner0")
+            .await;
+        assert!(matches!(result, Err(ScreepsError::InvalidParam(_))));
+        assert_eq!(transport.requests_to("/game/map-stats").len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_mock_market_endpoints() {
+        let order = json!({
+            "_id": "o1", "type": "sell", "resourceType": "energy", "price": 1.5,
+            "amount": 100, "remainingAmount": 100, "roomName": "E13S13"
+        });
+        let (api, transport) = mock_api();
+        transport
+            .on(
+                Get,
+                "/game/market/orders-index",
+                MockResponse::json(&json!({ "ok": 1, "list": [{ "_id": "energy", "count": 3 }] })),
+            )
+            .on(
+                Get,
+                "/game/market/orders",
+                MockResponse::json(&json!({ "ok": 1, "list": [order.clone()] })),
+            )
+            .on(
+                Get,
+                "/game/market/my-orders",
+                MockResponse::json(&json!({ "ok": 1, "shards": { "shard3": [order] } })),
+            )
+            .on(
+                Get,
+                "/game/market/stats",
+                MockResponse::json(&json!({
+                    "ok": 1,
+                    "stats": [{
+                        "resourceType": "energy", "date": "2024-01-01", "transactions": 5,
+                        "volume": 1000, "avgPrice": 1.2, "stddevPrice": 0.1
+                    }]
+                })),
+            );
+
+        let index = api.get_market_orders_index("shard3").await.unwrap();
+        assert_eq!(index.list.unwrap()[0].count, 3);
+        let orders = api.get_market_orders("energy", "shard3").await.unwrap();
+        assert_eq!(orders.list.unwrap()[0].r#type, crate::MarketOrderType::Sell);
+        let my_orders = api.get_my_market_orders().await.unwrap();
+        assert_eq!(my_orders.shards.unwrap()["shard3"].len(), 1);
+        let stats = api.get_market_stats("energy", "shard3").await.unwrap();
+        assert_eq!(stats.stats.unwrap()[0].volume, 1000);
+
+        let query = |path: &str, key: &str| transport.requests_to(path)[0].query(key);
+        assert_eq!(
+            query("/game/market/orders-index", "shard").as_deref(),
+            Some("shard3")
+        );
+        assert_eq!(
+            query("/game/market/orders", "resourceType").as_deref(),
+            Some("energy")
+        );
+        assert_eq!(
+            query("/game/market/orders", "shard").as_deref(),
+            Some("shard3")
+        );
+        assert_eq!(
+            transport.requests_to("/game/market/my-orders")[0].url,
+            api.http_client.build_url("/game/market/my-orders")
+        );
+        assert_eq!(
+            query("/game/market/stats", "resourceType").as_deref(),
+            Some("energy")
+        );
+        assert_eq!(
+            query("/game/market/stats", "shard").as_deref(),
+            Some("shard3")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_mock_money_history_pages() {
+        let entry = |id: &str| {
+            json!({
+                "_id": id, "date": "2024-01-01T00:00:00.000Z", "tick": 1, "user": "u1",
+                "type": "market.sell", "balance": 10.0, "change": 1.0
+            })
+        };
+        let (api, transport) = mock_api();
+        transport
+            .on(
+                Get,
+                "/user/money-history",
+                MockResponse::json(&json!({ "ok": 1, "page": 0, "list": [entry("a"), entry("b")], "hasMore": true })),
+            )
+            .on(
+                Get,
+                "/user/money-history",
+                MockResponse::json(&json!({ "ok": 1, "page": 1, "list": [entry("c")], "hasMore": false })),
+            );
+
+        let mut pages = api.money_history_pages();
+        let mut ids = vec![];
+        while let Some(page) = pages.next_page().await {
+            ids.extend(page.unwrap().into_iter().map(|entry| entry._id));
+        }
+        assert_eq!(ids, vec!["a", "b", "c"]);
+        assert_eq!(pages.page(), 2);
+        let requests = transport.requests_to("/user/money-history");
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].query("page").as_deref(), Some("0"));
+        assert_eq!(requests[1].query("page").as_deref(), Some("1"));
+
+        // 单独请求指定页
+        let (api, transport) = mock_api();
+        transport.on(
+            Get,
+            "/user/money-history",
+            MockResponse::json(&json!({ "ok": 1, "page": 5, "list": [], "hasMore": false })),
+        );
+        let history = api.get_money_history(5).await.unwrap();
+        assert!(history.list.unwrap().is_empty());
+        assert_eq!(
+            transport.requests_to("/user/money-history")[0]
+                .query("page")
+                .as_deref(),
+            Some("5")
+        );
+    }
 
     #[tokio::test]
     async fn test_get_my_info() {
@@ -223,6 +1521,16 @@ mod tests {
         assert_eq!(room_status.base_data.ok.unwrap(), 1);
     }
 
+    #[tokio::test]
+    async fn test_get_map_stats() {
+        let api = screeps_api_from_env!().unwrap();
+        let map_stats = api
+            .get_map_stats(&["E13S13", "E14S13"], "shard3", "owner0")
+            .await
+            .unwrap();
+        assert_eq!(map_stats.base_data.ok.unwrap(), 1);
+    }
+
     #[tokio::test]
     async fn test_get_shards() {
         let api = ScreepsApi::default();
@@ -236,4 +1544,98 @@ mod tests {
         let game_time = api.get_shard_time("shard3").await.unwrap();
         assert_eq!(game_time.base_data.ok.unwrap(), 1);
     }
+
+    #[tokio::test]
+    async fn test_get_branches() {
+        let api = screeps_api_from_env!().unwrap();
+        let branches = api.get_branches().await.unwrap();
+        assert_eq!(branches.base_data.ok.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_get_code() {
+        let api = screeps_api_from_env!().unwrap();
+        let code = api.get_code("default").await.unwrap();
+        assert_eq!(code.base_data.ok.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_get_memory() {
+        let api = screeps_api_from_env!().unwrap();
+        let memory = api.get_memory("", "shard3").await.unwrap();
+        assert_eq!(memory.base_data.ok.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_get_memory_segments() {
+        let api = screeps_api_from_env!().unwrap();
+        let segments = api.get_memory_segments(&[0, 1], "shard3").await.unwrap();
+        assert_eq!(segments.base_data.ok.unwrap(), 1);
+        assert_eq!(segments.data.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_get_market_orders_index() {
+        let api = screeps_api_from_env!().unwrap();
+        let index = api.get_market_orders_index("shard3").await.unwrap();
+        assert_eq!(index.base_data.ok.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_get_market_orders() {
+        let api = screeps_api_from_env!().unwrap();
+        let orders = api.get_market_orders("energy", "shard3").await.unwrap();
+        assert_eq!(orders.base_data.ok.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_get_market_stats() {
+        let api = screeps_api_from_env!().unwrap();
+        let stats = api.get_market_stats("energy", "shard3").await.unwrap();
+        assert_eq!(stats.base_data.ok.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_get_money_history() {
+        let api = screeps_api_from_env!().unwrap();
+        let mut pages = api.money_history_pages();
+        if let Some(page) = pages.next_page().await {
+            assert!(!page.unwrap().is_empty());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_memory_segment_validation() {
+        let api = ScreepsApi::default();
+        assert!(matches!(
+            api.get_memory_segments(&[1, 100], "shard3").await,
+            Err(ScreepsError::InvalidParam(_))
+        ));
+        assert!(matches!(
+            api.set_memory_segment(0, &"a".repeat(MAX_MEMORY_SEGMENT_SIZE + 1), "shard3")
+                .await,
+            Err(ScreepsError::InvalidParam(_))
+        ));
+        // 长度按字符计算，中文每个字符 3 字节但只算 1 个字符
+        assert!(matches!(
+            api.set_memory_segment(0, &"统".repeat(MAX_MEMORY_SEGMENT_SIZE + 1), "shard3")
+                .await,
+            Err(ScreepsError::InvalidParam(_))
+        ));
+
+        let (api, transport) = mock_api();
+        transport.on(
+            Post,
+            "/user/memory-segment",
+            MockResponse::json(&json!({ "ok": 1 })),
+        );
+        let data = "统".repeat(MAX_MEMORY_SEGMENT_SIZE);
+        api.set_memory_segment(0, &data, "shard3").await.unwrap();
+        assert_eq!(
+            transport.requests_to("/user/memory-segment")[0]
+                .json()
+                .unwrap()["data"],
+            data
+        );
+    }
 }
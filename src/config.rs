@@ -1,3 +1,10 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    path::PathBuf,
+    time::Duration,
+};
+
 /// Screeps 配置
 #[derive(Debug)]
 pub struct ScreepsConfig {
@@ -13,6 +20,14 @@ pub struct ScreepsConfig {
     pub secure: bool,
     /// 请求超时时间
     pub timeout: u64,
+    /// 是否检查响应的 `ok` 字段，开启后 `ok` 不为 1 的响应会转换为 `ScreepsError::Api`
+    pub check_ok: bool,
+    /// 请求失败时的重试策略
+    pub retry: RetryPolicy,
+    /// 地形缓存目录，设置后 `get_room_terrain_grid` 会优先读取缓存
+    pub terrain_cache_dir: Option<PathBuf>,
+    /// 批量查询（例如 `get_user_inventory`）同时进行的最大请求数
+    pub batch_concurrency: usize,
 }
 
 impl ScreepsConfig {
@@ -31,6 +46,10 @@ impl ScreepsConfig {
             host,
             secure,
             timeout,
+            check_ok: true,
+            retry: RetryPolicy::default(),
+            terrain_cache_dir: None,
+            batch_concurrency: 4,
         }
     }
 
@@ -64,6 +83,26 @@ impl ScreepsConfig {
         self.timeout = timeout;
     }
 
+    /// 设置是否检查响应的 `ok` 字段
+    pub fn with_check_ok(&mut self, check_ok: bool) {
+        self.check_ok = check_ok;
+    }
+
+    /// 设置重试策略
+    pub fn with_retry(&mut self, retry: RetryPolicy) {
+        self.retry = retry;
+    }
+
+    /// 设置地形缓存目录
+    pub fn with_terrain_cache_dir(&mut self, dir: PathBuf) {
+        self.terrain_cache_dir = Some(dir);
+    }
+
+    /// 设置批量查询同时进行的最大请求数
+    pub fn with_batch_concurrency(&mut self, batch_concurrency: usize) {
+        self.batch_concurrency = batch_concurrency;
+    }
+
     /// 构造游戏服务器请求 url 前缀 url
     pub fn build_base_url(&self) -> String {
         let protocol = if self.secure { "https" } else { "http" };
@@ -80,6 +119,92 @@ impl Default for ScreepsConfig {
             host: "screeps.com".to_string(),
             secure: true,
             timeout: 15,
+            check_ok: true,
+            retry: RetryPolicy::default(),
+            terrain_cache_dir: None,
+            batch_concurrency: 4,
+        }
+    }
+}
+
+/// 请求重试策略
+///
+/// 网络错误、5xx 和 429 响应会按指数退避重试，429 优先使用服务器返回的
+/// `Retry-After` 或 `x-ratelimit-reset` 作为等待时间，超过 `max_delay` 时不再等待。
+/// POST 请求不是幂等的，默认只在连接失败和 429 时重试
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// 最大尝试次数，包括第一次请求，1 表示不重试
+    pub max_attempts: u32,
+    /// 第一次重试前的等待时间，之后每次翻倍
+    pub base_delay: Duration,
+    /// 退避等待时间的上限
+    pub max_delay: Duration,
+    /// 是否给等待时间加上随机抖动，避免大量请求同时重试
+    pub jitter: bool,
+    /// POST 请求超时或返回 5xx 时是否也重试，服务器可能已经执行了请求，默认关闭
+    pub retry_post: bool,
+}
+
+impl RetryPolicy {
+    /// 不重试
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Default::default()
+        }
+    }
+
+    /// 第 attempt 次请求失败后的退避等待时间，attempt 从 1 开始
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exp = attempt.saturating_sub(1).min(31);
+        let delay = self.base_delay.saturating_mul(1 << exp).min(self.max_delay);
+        if !self.jitter {
+            return delay;
+        }
+        // 在 [delay / 2, delay] 之间随机
+        let random = RandomState::new().build_hasher().finish() as f64 / u64::MAX as f64;
+        delay.mul_f64(0.5 + random * 0.5)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            jitter: true,
+            retry_post: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_backoff() {
+        let policy = RetryPolicy {
+            max_attempts: 5,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(350),
+            jitter: false,
+            retry_post: false,
+        };
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(350));
+        assert_eq!(policy.backoff(100), Duration::from_millis(350));
+
+        let policy = RetryPolicy {
+            jitter: true,
+            ..policy
+        };
+        for attempt in 1..5 {
+            let delay = policy.backoff(attempt);
+            assert!(delay >= Duration::from_millis(50) && delay <= Duration::from_millis(350));
         }
     }
 }
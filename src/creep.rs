@@ -0,0 +1,493 @@
+//! Creep 身体部件和能力计算
+//!
+//! 根据 creep 的身体部件和强化计算攻击、治疗、采集、拆除、携带和移动能力，数值与游戏常量一致。
+//! 只有 `hits > 0` 的部件会计入能力。
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{resource::ResourceType, terrain::Terrain, types::Creep};
+
+/// ATTACK 部件每 tick 的伤害
+pub const ATTACK_POWER: f64 = 30.0;
+/// RANGED_ATTACK 部件每 tick 的伤害
+pub const RANGED_ATTACK_POWER: f64 = 10.0;
+/// HEAL 部件每 tick 在相邻位置的治疗量
+pub const HEAL_POWER: f64 = 12.0;
+/// HEAL 部件每 tick 远程治疗量
+pub const RANGED_HEAL_POWER: f64 = 4.0;
+/// WORK 部件每 tick 的拆除量
+pub const DISMANTLE_POWER: f64 = 50.0;
+/// WORK 部件每 tick 从 source 采集的能量
+pub const HARVEST_POWER: f64 = 2.0;
+/// WORK 部件每 tick 从 mineral 或 deposit 采集的资源
+pub const HARVEST_MINERAL_POWER: f64 = 1.0;
+/// 每个 CARRY 部件的容量
+pub const CARRY_CAPACITY: u32 = 50;
+/// 每个 MOVE 部件每 tick 减少的疲劳值
+pub const MOVE_POWER: u32 = 2;
+/// creep 的寿命
+pub const CREEP_LIFE_TIME: u64 = 1500;
+/// 带有 CLAIM 部件的 creep 的寿命
+pub const CREEP_CLAIM_LIFE_TIME: u64 = 600;
+
+/// 身体部件类型，未知的类型保存为 `BodyPartType::Unknown`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum BodyPartType {
+    Move,
+    Work,
+    Carry,
+    Attack,
+    RangedAttack,
+    Heal,
+    Claim,
+    Tough,
+    #[serde(untagged)]
+    Unknown(String),
+}
+
+impl BodyPartType {
+    /// 游戏中的部件名称，例如 `ranged_attack`
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Move => "move",
+            Self::Work => "work",
+            Self::Carry => "carry",
+            Self::Attack => "attack",
+            Self::RangedAttack => "ranged_attack",
+            Self::Heal => "heal",
+            Self::Claim => "claim",
+            Self::Tough => "tough",
+            Self::Unknown(name) => name,
+        }
+    }
+
+    /// 孵化所需的能量，未知部件为 0
+    pub fn cost(&self) -> u32 {
+        match self {
+            Self::Move | Self::Carry => 50,
+            Self::Work => 100,
+            Self::Attack => 80,
+            Self::RangedAttack => 150,
+            Self::Heal => 250,
+            Self::Claim => 600,
+            Self::Tough => 10,
+            Self::Unknown(_) => 0,
+        }
+    }
+}
+
+impl fmt::Display for BodyPartType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// 强化影响的动作
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BoostAction {
+    Harvest,
+    Build,
+    Repair,
+    Dismantle,
+    UpgradeController,
+    Attack,
+    RangedAttack,
+    RangedMassAttack,
+    Heal,
+    RangedHeal,
+    /// CARRY 部件的容量
+    Capacity,
+    /// MOVE 部件减少的疲劳值
+    Fatigue,
+    /// TOUGH 部件受到的伤害，倍率小于 1
+    Damage,
+}
+
+/// 强化化合物对指定部件和动作的倍率，没有效果时为 1
+pub fn boost_multiplier(part: &BodyPartType, boost: &ResourceType, action: BoostAction) -> f64 {
+    use BodyPartType as P;
+    use BoostAction as A;
+    use ResourceType as R;
+
+    match (part, action, boost) {
+        (P::Work, A::Harvest, R::UtriumOxide) => 3.0,
+        (P::Work, A::Harvest, R::UtriumAlkalide) => 5.0,
+        (P::Work, A::Harvest, R::CatalyzedUtriumAlkalide) => 7.0,
+        (P::Work, A::Build | A::Repair, R::LemergiumHydride) => 1.5,
+        (P::Work, A::Build | A::Repair, R::LemergiumAcid) => 1.8,
+        (P::Work, A::Build | A::Repair, R::CatalyzedLemergiumAcid) => 2.0,
+        (P::Work, A::Dismantle, R::ZynthiumHydride) => 2.0,
+        (P::Work, A::Dismantle, R::ZynthiumAcid) => 3.0,
+        (P::Work, A::Dismantle, R::CatalyzedZynthiumAcid) => 4.0,
+        (P::Work, A::UpgradeController, R::GhodiumHydride) => 1.5,
+        (P::Work, A::UpgradeController, R::GhodiumAcid) => 1.8,
+        (P::Work, A::UpgradeController, R::CatalyzedGhodiumAcid) => 2.0,
+        (P::Attack, A::Attack, R::UtriumHydride) => 2.0,
+        (P::Attack, A::Attack, R::UtriumAcid) => 3.0,
+        (P::Attack, A::Attack, R::CatalyzedUtriumAcid) => 4.0,
+        (P::RangedAttack, A::RangedAttack | A::RangedMassAttack, R::KeaniumOxide) => 2.0,
+        (P::RangedAttack, A::RangedAttack | A::RangedMassAttack, R::KeaniumAlkalide) => 3.0,
+        (P::RangedAttack, A::RangedAttack | A::RangedMassAttack, R::CatalyzedKeaniumAlkalide) => {
+            4.0
+        }
+        (P::Heal, A::Heal | A::RangedHeal, R::LemergiumOxide) => 2.0,
+        (P::Heal, A::Heal | A::RangedHeal, R::LemergiumAlkalide) => 3.0,
+        (P::Heal, A::Heal | A::RangedHeal, R::CatalyzedLemergiumAlkalide) => 4.0,
+        (P::Carry, A::Capacity, R::KeaniumHydride) => 2.0,
+        (P::Carry, A::Capacity, R::KeaniumAcid) => 3.0,
+        (P::Carry, A::Capacity, R::CatalyzedKeaniumAcid) => 4.0,
+        (P::Move, A::Fatigue, R::ZynthiumOxide) => 2.0,
+        (P::Move, A::Fatigue, R::ZynthiumAlkalide) => 3.0,
+        (P::Move, A::Fatigue, R::CatalyzedZynthiumAlkalide) => 4.0,
+        (P::Tough, A::Damage, R::GhodiumOxide) => 0.7,
+        (P::Tough, A::Damage, R::GhodiumAlkalide) => 0.5,
+        (P::Tough, A::Damage, R::CatalyzedGhodiumAlkalide) => 0.3,
+        _ => 1.0,
+    }
+}
+
+/// 每个身体部件在指定地形上移动一格产生的疲劳值，墙上没有道路时无法移动
+pub fn terrain_fatigue(terrain: Terrain, road: bool) -> Option<u32> {
+    match (terrain, road) {
+        (_, true) => Some(1),
+        (Terrain::Plain, false) => Some(2),
+        (Terrain::Swamp, false) => Some(10),
+        (Terrain::Wall, false) => None,
+    }
+}
+
+/// Creep 对象的 Body 字段
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CreepBodyPart {
+    #[serde(rename = "type")]
+    pub part_type: BodyPartType,
+    pub hits: Option<i32>,
+    pub boost: Option<ResourceType>,
+}
+
+impl CreepBodyPart {
+    /// 部件是否还有血量，没有 `hits` 字段时视为完好
+    pub fn is_active(&self) -> bool {
+        self.hits.is_none_or(|hits| hits > 0)
+    }
+
+    /// 强化对指定动作的倍率，没有强化时为 1
+    pub fn multiplier(&self, action: BoostAction) -> f64 {
+        self.boost.as_ref().map_or(1.0, |boost| {
+            boost_multiplier(&self.part_type, boost, action)
+        })
+    }
+}
+
+impl Creep {
+    fn parts(&self) -> &[CreepBodyPart] {
+        self.body.as_deref().unwrap_or_default()
+    }
+
+    /// 指定类型的部件数量，包括已经损坏的部件
+    pub fn part_count(&self, part_type: BodyPartType) -> usize {
+        self.parts()
+            .iter()
+            .filter(|part| part.part_type == part_type)
+            .count()
+    }
+
+    /// 指定类型、仍然有效的部件数量
+    pub fn active_part_count(&self, part_type: BodyPartType) -> usize {
+        self.parts()
+            .iter()
+            .filter(|part| part.part_type == part_type && part.is_active())
+            .count()
+    }
+
+    /// 按有效部件累加 `base * 强化倍率`
+    fn power(&self, part_type: BodyPartType, action: BoostAction, base: f64) -> f64 {
+        self.parts()
+            .iter()
+            .filter(|part| part.part_type == part_type && part.is_active())
+            .map(|part| base * part.multiplier(action))
+            .sum()
+    }
+
+    /// 每 tick 近战伤害
+    pub fn attack_power(&self) -> f64 {
+        self.power(BodyPartType::Attack, BoostAction::Attack, ATTACK_POWER)
+    }
+
+    /// 每 tick 远程攻击伤害，也是 rangedMassAttack 对距离 1 目标的伤害
+    pub fn ranged_attack_power(&self) -> f64 {
+        self.power(
+            BodyPartType::RangedAttack,
+            BoostAction::RangedAttack,
+            RANGED_ATTACK_POWER,
+        )
+    }
+
+    /// 每 tick 对相邻目标的治疗量
+    pub fn heal_power(&self) -> f64 {
+        self.power(BodyPartType::Heal, BoostAction::Heal, HEAL_POWER)
+    }
+
+    /// 每 tick 远程治疗量
+    pub fn ranged_heal_power(&self) -> f64 {
+        self.power(
+            BodyPartType::Heal,
+            BoostAction::RangedHeal,
+            RANGED_HEAL_POWER,
+        )
+    }
+
+    /// 每 tick 拆除量
+    pub fn dismantle_power(&self) -> f64 {
+        self.power(BodyPartType::Work, BoostAction::Dismantle, DISMANTLE_POWER)
+    }
+
+    /// 每 tick 从 source 采集的能量
+    pub fn harvest_power(&self) -> f64 {
+        self.power(BodyPartType::Work, BoostAction::Harvest, HARVEST_POWER)
+    }
+
+    /// 每 tick 从 mineral 或 deposit 采集的资源
+    pub fn harvest_mineral_power(&self) -> f64 {
+        self.power(
+            BodyPartType::Work,
+            BoostAction::Harvest,
+            HARVEST_MINERAL_POWER,
+        )
+    }
+
+    /// 有效 CARRY 部件的总容量
+    pub fn carry_capacity(&self) -> u32 {
+        self.power(
+            BodyPartType::Carry,
+            BoostAction::Capacity,
+            CARRY_CAPACITY as f64,
+        ) as u32
+    }
+
+    /// 考虑 TOUGH 强化减伤后还能承受的伤害
+    pub fn effective_hits(&self) -> f64 {
+        self.parts()
+            .iter()
+            .map(|part| {
+                let hits = part.hits.unwrap_or(0).max(0) as f64;
+                hits / part.multiplier(BoostAction::Damage)
+            })
+            .sum()
+    }
+
+    /// 产生疲劳的部件数量，与游戏的 `calcWeight` 一致：
+    /// - 除 MOVE 和 CARRY 外的部件都计入，已经损坏的部件也计入
+    /// - CARRY 部件从身体末尾开始依次装载资源，只有装有资源且未损坏的 CARRY 部件计入
+    fn weight(&self) -> u32 {
+        let mut used = self.store.total().max(0) as f64;
+        let mut weight = 0;
+        for part in self.parts().iter().rev() {
+            match part.part_type {
+                BodyPartType::Move => {}
+                BodyPartType::Carry => {
+                    if !part.is_active() || used <= 0.0 {
+                        continue;
+                    }
+                    used -= CARRY_CAPACITY as f64 * part.multiplier(BoostAction::Capacity);
+                    weight += 1;
+                }
+                _ => weight += 1,
+            }
+        }
+        weight
+    }
+
+    /// 在指定地形上移动一格产生的疲劳值，无法通过时返回 `None`
+    pub fn move_fatigue(&self, terrain: Terrain, road: bool) -> Option<u32> {
+        terrain_fatigue(terrain, road).map(|fatigue| fatigue * self.weight())
+    }
+
+    /// 每 tick 有效 MOVE 部件减少的疲劳值
+    pub fn fatigue_recovery(&self) -> u32 {
+        self.power(BodyPartType::Move, BoostAction::Fatigue, MOVE_POWER as f64) as u32
+    }
+
+    /// 在指定地形上持续移动时平均每格需要的 tick 数，无法移动时返回 `None`
+    pub fn ticks_per_tile(&self, terrain: Terrain, road: bool) -> Option<u32> {
+        let fatigue = self.move_fatigue(terrain, road)?;
+        if fatigue == 0 {
+            return Some(1);
+        }
+        let recovery = self.fatigue_recovery();
+        if recovery == 0 {
+            return None;
+        }
+        Some(fatigue.div_ceil(recovery).max(1))
+    }
+
+    /// 孵化所有身体部件需要的能量，不包括强化
+    pub fn body_cost(&self) -> u32 {
+        self.parts().iter().map(|part| part.part_type.cost()).sum()
+    }
+
+    /// 寿命，带有 CLAIM 部件时为 600
+    pub fn life_time(&self) -> u64 {
+        if self.part_count(BodyPartType::Claim) > 0 {
+            CREEP_CLAIM_LIFE_TIME
+        } else {
+            CREEP_LIFE_TIME
+        }
+    }
+
+    /// 剩余寿命，正在孵化或没有 `ageTime` 时返回 `None`
+    ///
+    /// 参数：
+    /// - game_time: 当前游戏时间，可以通过 `get_shard_time` 获取
+    pub fn ticks_to_live(&self, game_time: u64) -> Option<u64> {
+        if self.spawning == Some(true) {
+            return None;
+        }
+        self.age_time
+            .map(|age_time| age_time.saturating_sub(game_time))
+    }
+
+    /// 按剩余寿命折算的身体部件能量价值，正在孵化时按完整寿命计算
+    pub fn remaining_value(&self, game_time: u64) -> f64 {
+        let life_time = self.life_time();
+        let ticks = self
+            .ticks_to_live(game_time)
+            .unwrap_or(life_time)
+            .min(life_time);
+        self.body_cost() as f64 * ticks as f64 / life_time as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::RoomObject;
+
+    fn creep(body: &str, store: &str) -> Creep {
+        let json = format!(
+            r#"{{ "_id": "c1", "type": "creep", "x": 10, "y": 10, "room": "E13S13",
+                 "name": "attacker", "hits": 500, "hitsMax": 500, "user": "u1",
+                 "spawning": false, "fatigue": 0, "ageTime": 11500,
+                 "body": {}, "store": {}, "storeCapacity": 100 }}"#,
+            body, store
+        );
+        match serde_json::from_str(&json).unwrap() {
+            RoomObject::Creep(creep) => creep,
+            other => panic!("unexpected object {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_body_part_serde() {
+        let parts: Vec<CreepBodyPart> = serde_json::from_str(
+            r#"[{ "type": "ranged_attack", "hits": 100, "boost": "XKHO2" },
+                { "type": "wings", "hits": 100 }]"#,
+        )
+        .unwrap();
+        assert_eq!(parts[0].part_type, BodyPartType::RangedAttack);
+        assert_eq!(parts[0].boost, Some(ResourceType::CatalyzedKeaniumAlkalide));
+        assert_eq!(
+            parts[1].part_type,
+            BodyPartType::Unknown("wings".to_string())
+        );
+        assert_eq!(
+            serde_json::to_value(&parts[0].part_type).unwrap(),
+            "ranged_attack"
+        );
+        assert_eq!(serde_json::to_value(&parts[1].part_type).unwrap(), "wings");
+    }
+
+    #[test]
+    fn test_combat_power() {
+        let creep = creep(
+            r#"[{ "type": "tough", "hits": 100, "boost": "XGHO2" },
+                { "type": "attack", "hits": 0, "boost": "XUH2O" },
+                { "type": "attack", "hits": 100, "boost": "XUH2O" },
+                { "type": "ranged_attack", "hits": 100, "boost": "KO" },
+                { "type": "heal", "hits": 100, "boost": "LHO2" },
+                { "type": "heal", "hits": 100 },
+                { "type": "work", "hits": 100, "boost": "ZH2O" },
+                { "type": "move", "hits": 100 }]"#,
+            "{}",
+        );
+        assert_eq!(creep.part_count(BodyPartType::Attack), 2);
+        assert_eq!(creep.active_part_count(BodyPartType::Attack), 1);
+        assert_eq!(creep.attack_power(), 120.0);
+        assert_eq!(creep.ranged_attack_power(), 20.0);
+        assert_eq!(creep.heal_power(), 48.0);
+        assert_eq!(creep.ranged_heal_power(), 16.0);
+        assert_eq!(creep.dismantle_power(), 150.0);
+        assert_eq!(creep.harvest_power(), 2.0);
+        assert!((creep.effective_hits() - (100.0 / 0.3 + 600.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_carry_and_move() {
+        let body = r#"[{ "type": "work", "hits": 100, "boost": "XUHO2" },
+                       { "type": "carry", "hits": 100 },
+                       { "type": "carry", "hits": 100, "boost": "KH" },
+                       { "type": "move", "hits": 100 }]"#;
+        let empty = creep(body, "{}");
+        assert_eq!(empty.harvest_power(), 14.0);
+        assert_eq!(empty.harvest_mineral_power(), 7.0);
+        assert_eq!(empty.carry_capacity(), 150);
+        // 空的 CARRY 部件不产生疲劳
+        assert_eq!(empty.move_fatigue(Terrain::Plain, false), Some(2));
+        assert_eq!(empty.ticks_per_tile(Terrain::Plain, false), Some(1));
+        assert_eq!(empty.ticks_per_tile(Terrain::Swamp, false), Some(5));
+        assert_eq!(empty.ticks_per_tile(Terrain::Wall, false), None);
+        assert_eq!(empty.ticks_per_tile(Terrain::Wall, true), Some(1));
+
+        let loaded = creep(body, r#"{ "energy": 120 }"#);
+        assert_eq!(loaded.move_fatigue(Terrain::Plain, false), Some(6));
+        assert_eq!(loaded.ticks_per_tile(Terrain::Plain, false), Some(3));
+        assert_eq!(loaded.ticks_per_tile(Terrain::Plain, true), Some(2));
+    }
+
+    #[test]
+    fn test_weight_with_destroyed_parts() {
+        // 损坏的 WORK 部件仍然产生疲劳，资源先装入末尾强化过的 CARRY 部件
+        let creep = self::creep(
+            r#"[{ "type": "work", "hits": 0 },
+                { "type": "carry", "hits": 100 },
+                { "type": "carry", "hits": 100, "boost": "KH" },
+                { "type": "move", "hits": 100 }]"#,
+            r#"{ "energy": 60 }"#,
+        );
+        assert_eq!(creep.move_fatigue(Terrain::Plain, false), Some(4));
+
+        // 损坏的 CARRY 部件不装载资源，也不产生疲劳
+        let creep = self::creep(
+            r#"[{ "type": "carry", "hits": 100 },
+                { "type": "carry", "hits": 0 },
+                { "type": "move", "hits": 100 }]"#,
+            r#"{ "energy": 50 }"#,
+        );
+        assert_eq!(creep.move_fatigue(Terrain::Plain, false), Some(2));
+        assert_eq!(creep.ticks_per_tile(Terrain::Plain, false), Some(1));
+    }
+
+    #[test]
+    fn test_ticks_to_live_and_value() {
+        let creep = creep(
+            r#"[{ "type": "work", "hits": 100 }, { "type": "move", "hits": 100 }]"#,
+            "{}",
+        );
+        assert_eq!(creep.body_cost(), 150);
+        assert_eq!(creep.life_time(), CREEP_LIFE_TIME);
+        assert_eq!(creep.ticks_to_live(10000), Some(1500));
+        assert_eq!(creep.ticks_to_live(11000), Some(500));
+        assert_eq!(creep.ticks_to_live(12000), Some(0));
+        assert_eq!(creep.remaining_value(11000), 50.0);
+
+        let claimer = self::creep(
+            r#"[{ "type": "claim", "hits": 100 }, { "type": "move", "hits": 100 }]"#,
+            "{}",
+        );
+        assert_eq!(claimer.life_time(), CREEP_CLAIM_LIFE_TIME);
+        assert_eq!(claimer.remaining_value(11200), 650.0 * 300.0 / 600.0);
+    }
+}
@@ -0,0 +1,395 @@
+//! 房间防御评估
+//!
+//! 根据 `get_room_objects` 返回的房间对象汇总 tower、rampart、wall、安全模式和 creep 战斗力，
+//! 用于在进攻前评估侦察到的房间。
+
+use serde::Serialize;
+
+use crate::{
+    resource::ResourceType,
+    room_name::RoomName,
+    terrain::ROOM_SIZE,
+    types::{Creep, RoomObject},
+};
+
+/// tower 在最佳距离内的伤害
+pub const TOWER_POWER_ATTACK: f64 = 600.0;
+/// tower 伤害不衰减的最大距离
+pub const TOWER_OPTIMAL_RANGE: u32 = 5;
+/// tower 伤害衰减到最低的距离
+pub const TOWER_FALLOFF_RANGE: u32 = 20;
+/// 最远距离时伤害衰减的比例
+pub const TOWER_FALLOFF: f64 = 0.75;
+/// tower 每次行动消耗的能量
+pub const TOWER_ENERGY_COST: i64 = 10;
+
+/// 单个 tower 在指定距离上的伤害，距离 5 以内为 600，20 及以上为 150，中间线性衰减
+pub fn tower_damage(range: u32) -> f64 {
+    let range = range.clamp(TOWER_OPTIMAL_RANGE, TOWER_FALLOFF_RANGE);
+    let falloff =
+        (range - TOWER_OPTIMAL_RANGE) as f64 / (TOWER_FALLOFF_RANGE - TOWER_OPTIMAL_RANGE) as f64;
+    TOWER_POWER_ATTACK * (1.0 - TOWER_FALLOFF * falloff)
+}
+
+/// 房间内的 tower
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct TowerInfo {
+    pub id: String,
+    pub x: i32,
+    pub y: i32,
+    pub energy: i64,
+}
+
+impl TowerInfo {
+    /// 能量是否足够攻击
+    pub fn is_active(&self) -> bool {
+        self.energy >= TOWER_ENERGY_COST
+    }
+
+    /// 对指定位置的伤害，能量不足时为 0
+    pub fn damage_at(&self, x: i32, y: i32) -> f64 {
+        if !self.is_active() {
+            return 0.0;
+        }
+        let range = self.x.abs_diff(x).max(self.y.abs_diff(y));
+        tower_damage(range)
+    }
+}
+
+/// rampart 或 wall 的血量汇总，没有 `hits` 的 wall（例如新手区边界）不计入
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct BarrierStats {
+    pub count: usize,
+    pub total_hits: i64,
+    pub min_hits: Option<i32>,
+    pub max_hits: Option<i32>,
+}
+
+impl BarrierStats {
+    fn add(&mut self, hits: i32) {
+        self.count += 1;
+        self.total_hits += hits as i64;
+        self.min_hits = Some(self.min_hits.map_or(hits, |min| min.min(hits)));
+        self.max_hits = Some(self.max_hits.map_or(hits, |max| max.max(hits)));
+    }
+}
+
+/// 控制器安全模式状态
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct SafeModeState {
+    /// 安全模式剩余的 tick 数，未开启时为 `None`
+    pub active_ticks: Option<u64>,
+    /// 可用的安全模式次数
+    pub available: i32,
+    /// 冷却剩余的 tick 数，不在冷却中时为 `None`
+    pub cooldown_ticks: Option<u64>,
+}
+
+impl SafeModeState {
+    /// 是否开启了安全模式
+    pub fn is_active(&self) -> bool {
+        self.active_ticks.is_some()
+    }
+
+    /// 是否可以立即开启安全模式
+    pub fn can_activate(&self) -> bool {
+        !self.is_active() && self.available > 0 && self.cooldown_ticks.is_none()
+    }
+}
+
+/// 一组 creep 每 tick 的战斗力，已计入强化和损坏的部件
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct CreepForce {
+    pub count: usize,
+    pub attack: f64,
+    pub ranged_attack: f64,
+    pub heal: f64,
+    pub ranged_heal: f64,
+    pub dismantle: f64,
+    /// 考虑 TOUGH 强化后的有效血量
+    pub effective_hits: f64,
+}
+
+impl CreepForce {
+    fn add(&mut self, creep: &Creep) {
+        self.count += 1;
+        self.attack += creep.attack_power();
+        self.ranged_attack += creep.ranged_attack_power();
+        self.heal += creep.heal_power();
+        self.ranged_heal += creep.ranged_heal_power();
+        self.dismantle += creep.dismantle_power();
+        self.effective_hits += creep.effective_hits();
+    }
+
+    /// 近战和远程伤害之和
+    pub fn dps(&self) -> f64 {
+        self.attack + self.ranged_attack
+    }
+}
+
+/// 房间防御报告
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct RoomDefenseReport {
+    pub room: RoomName,
+    /// 生成报告时的游戏时间
+    pub game_time: u64,
+    /// 房间所有者的用户 id，房间未被占领时为预定者
+    pub owner: Option<String>,
+    pub controller_level: Option<i32>,
+    pub safe_mode: SafeModeState,
+    pub towers: Vec<TowerInfo>,
+    pub ramparts: BarrierStats,
+    pub walls: BarrierStats,
+    /// 房间所有者的 creep
+    pub defenders: CreepForce,
+    /// 不属于房间所有者的 creep，包括 invader 和 source keeper
+    pub hostiles: CreepForce,
+    /// 类型为 tower、rampart、constructedWall 或 creep 但没有解析成功的对象数量，
+    /// 这些对象没有计入报告，不为 0 时报告会低估房间的防御和敌方数量
+    pub unparsed: usize,
+}
+
+impl RoomDefenseReport {
+    /// 根据房间对象生成报告
+    ///
+    /// 参数：
+    /// - room: 房间名
+    /// - objects: 房间内的对象，可以通过 `get_room_objects` 获取
+    /// - game_time: 当前游戏时间，用于计算安全模式剩余时间
+    pub fn from_objects(room: RoomName, objects: &[RoomObject], game_time: u64) -> Self {
+        let controller = objects.iter().find_map(|object| match object {
+            RoomObject::Controller(controller) => Some(controller),
+            _ => None,
+        });
+        let owner = controller.and_then(|controller| {
+            controller.user.clone().or_else(|| {
+                controller
+                    .reservation
+                    .as_ref()
+                    .map(|reservation| reservation.user.clone())
+            })
+        });
+        let remaining = |end: Option<i64>| {
+            end.and_then(|end| u64::try_from(end).ok())
+                .filter(|end| *end > game_time)
+                .map(|end| end - game_time)
+        };
+        let safe_mode = controller
+            .map(|controller| SafeModeState {
+                active_ticks: remaining(controller.safe_mode.map(i64::from)),
+                available: controller.safe_mode_available.unwrap_or(0),
+                cooldown_ticks: remaining(controller.safe_mode_cooldown),
+            })
+            .unwrap_or_default();
+
+        let mut report = Self {
+            room,
+            game_time,
+            owner,
+            controller_level: controller.map(|controller| controller.level),
+            safe_mode,
+            towers: Vec::new(),
+            ramparts: BarrierStats::default(),
+            walls: BarrierStats::default(),
+            defenders: CreepForce::default(),
+            hostiles: CreepForce::default(),
+            unparsed: 0,
+        };
+        for object in objects {
+            match object {
+                RoomObject::Tower(tower) => report.towers.push(TowerInfo {
+                    id: tower.base_object._id.clone(),
+                    x: tower.base_object.x,
+                    y: tower.base_object.y,
+                    energy: tower.store.get(ResourceType::Energy),
+                }),
+                RoomObject::Rampart(rampart) => report.ramparts.add(rampart.hits),
+                RoomObject::ConstructedWall(wall) => {
+                    if let Some(hits) = wall.hits {
+                        report.walls.add(hits);
+                    }
+                }
+                RoomObject::Creep(creep) => {
+                    if report.owner.as_deref() == Some(creep.user.as_str()) {
+                        report.defenders.add(creep);
+                    } else {
+                        report.hostiles.add(creep);
+                    }
+                }
+                RoomObject::Unknown(unknown)
+                    if matches!(
+                        unknown.object_type.as_str(),
+                        "tower" | "rampart" | "constructedWall" | "creep"
+                    ) =>
+                {
+                    report.unparsed += 1;
+                }
+                _ => {}
+            }
+        }
+        report
+    }
+
+    /// 是否所有与防御相关的对象都计入了报告
+    pub fn is_complete(&self) -> bool {
+        self.unparsed == 0
+    }
+
+    /// 有足够能量攻击的 tower 数量
+    pub fn active_tower_count(&self) -> usize {
+        self.towers.iter().filter(|tower| tower.is_active()).count()
+    }
+
+    /// tower 的能量总和
+    pub fn tower_energy(&self) -> i64 {
+        self.towers.iter().map(|tower| tower.energy).sum()
+    }
+
+    /// 所有 tower 对指定位置的伤害之和
+    pub fn tower_damage_at(&self, x: i32, y: i32) -> f64 {
+        self.towers.iter().map(|tower| tower.damage_at(x, y)).sum()
+    }
+
+    /// 房间内 tower 伤害最低的位置和伤害
+    pub fn min_tower_damage(&self) -> (i32, i32, f64) {
+        self.positions()
+            .map(|(x, y)| (x, y, self.tower_damage_at(x, y)))
+            .min_by(|a, b| a.2.total_cmp(&b.2))
+            .unwrap_or_default()
+    }
+
+    /// 房间内 tower 伤害最高的位置和伤害
+    pub fn max_tower_damage(&self) -> (i32, i32, f64) {
+        self.positions()
+            .map(|(x, y)| (x, y, self.tower_damage_at(x, y)))
+            .max_by(|a, b| a.2.total_cmp(&b.2))
+            .unwrap_or_default()
+    }
+
+    fn positions(&self) -> impl Iterator<Item = (i32, i32)> {
+        let size = ROOM_SIZE as i32;
+        (0..size).flat_map(move |y| (0..size).map(move |x| (x, y)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::UnknownObject;
+
+    fn objects(json: &str) -> Vec<RoomObject> {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_tower_damage() {
+        assert_eq!(tower_damage(0), 600.0);
+        assert_eq!(tower_damage(5), 600.0);
+        assert_eq!(tower_damage(20), 150.0);
+        assert_eq!(tower_damage(40), 150.0);
+        assert_eq!(tower_damage(10), 450.0);
+    }
+
+    #[test]
+    fn test_room_defense_report() {
+        let objects = objects(
+            r#"[
+            { "_id": "ctrl", "type": "controller", "x": 20, "y": 20, "room": "E13S13",
+              "level": 7, "user": "owner", "safeMode": 1100, "safeModeAvailable": 2,
+              "safeModeCooldown": 900 },
+            { "_id": "t1", "type": "tower", "x": 10, "y": 10, "room": "E13S13", "hits": 3000,
+              "hitsMax": 3000, "notifyWhenAttacked": true, "user": "owner",
+              "store": { "energy": 1000 }, "storeCapacityResource": { "energy": 1000 },
+              "actionLog": {} },
+            { "_id": "t2", "type": "tower", "x": 40, "y": 40, "room": "E13S13", "hits": 3000,
+              "hitsMax": 3000, "notifyWhenAttacked": true, "user": "owner",
+              "store": { "energy": 5 }, "storeCapacityResource": { "energy": 1000 },
+              "actionLog": {} },
+            { "_id": "r1", "type": "rampart", "x": 11, "y": 10, "room": "E13S13",
+              "hits": 1000000, "hitsMax": 10000000, "user": "owner", "notifyWhenAttacked": true },
+            { "_id": "r2", "type": "rampart", "x": 12, "y": 10, "room": "E13S13",
+              "hits": 300000, "hitsMax": 10000000, "user": "owner", "notifyWhenAttacked": true },
+            { "_id": "w1", "type": "constructedWall", "x": 13, "y": 10, "room": "E13S13",
+              "hits": 5000000, "hitsMax": 300000000 },
+            { "_id": "w2", "type": "constructedWall", "x": 14, "y": 10, "room": "E13S13" },
+            { "_id": "c1", "type": "creep", "x": 15, "y": 15, "room": "E13S13", "name": "defender",
+              "hits": 100, "hitsMax": 100, "user": "owner", "store": {},
+              "body": [{ "type": "attack", "hits": 100, "boost": "UH" }] },
+            { "_id": "c2", "type": "creep", "x": 2, "y": 2, "room": "E13S13", "name": "healer",
+              "hits": 200, "hitsMax": 200, "user": "enemy", "store": {},
+              "body": [{ "type": "heal", "hits": 100, "boost": "XLHO2" },
+                       { "type": "ranged_attack", "hits": 100 }] }
+        ]"#,
+        );
+        let room = "E13S13".parse().unwrap();
+        let report = RoomDefenseReport::from_objects(room, &objects, 1000);
+
+        assert_eq!(report.owner.as_deref(), Some("owner"));
+        assert_eq!(report.controller_level, Some(7));
+        assert_eq!(report.safe_mode.active_ticks, Some(100));
+        assert_eq!(report.safe_mode.cooldown_ticks, None);
+        assert!(report.safe_mode.is_active());
+        assert!(!report.safe_mode.can_activate());
+
+        assert_eq!(report.towers.len(), 2);
+        assert_eq!(report.active_tower_count(), 1);
+        assert_eq!(report.tower_energy(), 1005);
+        assert_eq!(report.tower_damage_at(12, 12), 600.0);
+        assert_eq!(report.tower_damage_at(20, 10), 450.0);
+        assert_eq!(report.max_tower_damage().2, 600.0);
+        assert_eq!(report.min_tower_damage().2, 150.0);
+
+        assert_eq!(
+            report.ramparts,
+            BarrierStats {
+                count: 2,
+                total_hits: 1_300_000,
+                min_hits: Some(300_000),
+                max_hits: Some(1_000_000),
+            }
+        );
+        assert_eq!(report.walls.count, 1);
+        assert_eq!(report.walls.total_hits, 5_000_000);
+
+        assert_eq!(report.defenders.count, 1);
+        assert_eq!(report.defenders.attack, 60.0);
+        assert_eq!(report.hostiles.count, 1);
+        assert_eq!(report.hostiles.heal, 48.0);
+        assert_eq!(report.hostiles.ranged_heal, 16.0);
+        assert_eq!(report.hostiles.dps(), 10.0);
+        assert!(report.is_complete());
+    }
+
+    #[test]
+    fn test_unparsed_objects() {
+        let unknown = |object_type: &str| {
+            RoomObject::Unknown(UnknownObject {
+                object_type: object_type.to_string(),
+                data: Default::default(),
+            })
+        };
+        let objects = vec![
+            unknown("tower"),
+            unknown("creep"),
+            unknown("scoreCollector"),
+        ];
+        let report = RoomDefenseReport::from_objects("E13S13".parse().unwrap(), &objects, 1);
+        assert!(report.towers.is_empty());
+        assert_eq!(report.unparsed, 2);
+        assert!(!report.is_complete());
+    }
+
+    #[test]
+    fn test_unowned_room() {
+        let objects = objects(
+            r#"[{ "_id": "c1", "type": "creep", "x": 15, "y": 15, "room": "E15S15",
+                  "name": "Invader", "hits": 100, "hitsMax": 100, "user": "2", "store": {},
+                  "body": [{ "type": "attack", "hits": 100 }] }]"#,
+        );
+        let report = RoomDefenseReport::from_objects("E15S15".parse().unwrap(), &objects, 1);
+        assert_eq!(report.owner, None);
+        assert_eq!(report.safe_mode, SafeModeState::default());
+        assert_eq!(report.hostiles.attack, 30.0);
+        assert_eq!(report.min_tower_damage().2, 0.0);
+    }
+}
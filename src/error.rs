@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use reqwest::Error as ReqwestError;
 use serde_json::Error as SerdeError;
 use thiserror::Error;
@@ -13,17 +15,136 @@ pub enum ScreepsError {
     #[error("JSON parse failed: {0}")]
     Json(#[from] SerdeError),
 
-    /// api 接口报错
+    /// api 接口报错，即响应中 `ok` 不为 1
     #[error("API error: {0}")]
-    Api(String),
+    Api(ApiError),
+
+    /// 超过速率限制（429），重试后仍然失败
+    #[error("Rate limited on {path}, retry after {retry_after:?}")]
+    RateLimited {
+        /// 请求路径
+        path: String,
+        /// 服务器要求的等待时间
+        retry_after: Option<Duration>,
+    },
+
+    /// 服务器返回非成功的状态码，重试后仍然失败
+    #[error("HTTP status {status} on {path}: {body}")]
+    Status {
+        /// 请求路径
+        path: String,
+        /// http 状态码
+        status: u16,
+        /// 响应内容
+        body: String,
+    },
 
     /// auth 认证失败
     #[error("Auth failed")]
     Auth,
 
+    /// 参数错误，请求发送前就被拒绝
+    #[error("Invalid param: {0}")]
+    InvalidParam(String),
+
+    /// websocket 连接失败
+    #[error("WebSocket error: {0}")]
+    WebSocket(String),
+
+    /// 数据解码失败，例如 gz 压缩的内存数据
+    #[error("Decode failed: {0}")]
+    Decode(String),
+
     /// 配置错误
     #[error("Invalid config: {0}")]
     Config(String),
+
+    /// 文件读写失败
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
 }
 
 pub type ScreepsResult<T> = Result<T, ScreepsError>;
+
+/// api 接口返回的错误
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("{path}: {message}")]
+pub struct ApiError {
+    /// 请求路径
+    pub path: String,
+    /// 错误种类
+    pub kind: ApiErrorKind,
+    /// 服务器返回的原始错误信息
+    pub message: String,
+}
+
+impl ApiError {
+    /// 根据服务器返回的错误信息构造错误，自动识别错误种类
+    pub fn new(path: &str, message: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            kind: ApiErrorKind::from_message(message),
+            message: message.to_string(),
+        }
+    }
+}
+
+/// api 错误种类
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApiErrorKind {
+    /// 未认证或 token 失效
+    NotAuthorized,
+    /// 房间名不合法或房间不存在
+    InvalidRoom,
+    /// 用户不存在
+    UserNotFound,
+    /// 超过速率限制
+    RateLimited,
+    /// 参数错误
+    InvalidParams,
+    /// 其他错误
+    Other,
+}
+
+impl ApiErrorKind {
+    /// 根据服务器返回的错误信息识别错误种类
+    pub fn from_message(message: &str) -> Self {
+        let message = message.to_lowercase();
+        if message.contains("not authorized") || message.contains("unauthorized") {
+            Self::NotAuthorized
+        } else if message.contains("invalid room") {
+            Self::InvalidRoom
+        } else if message.contains("user not found") {
+            Self::UserNotFound
+        } else if message.contains("rate limit") || message.contains("too many requests") {
+            Self::RateLimited
+        } else if message.contains("invalid params") {
+            Self::InvalidParams
+        } else {
+            Self::Other
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_api_error_kind() {
+        let cases = [
+            ("not authorized", ApiErrorKind::NotAuthorized),
+            ("invalid room", ApiErrorKind::InvalidRoom),
+            ("invalid room name", ApiErrorKind::InvalidRoom),
+            ("user not found", ApiErrorKind::UserNotFound),
+            ("Rate limit exceeded", ApiErrorKind::RateLimited),
+            ("invalid params", ApiErrorKind::InvalidParams),
+            ("something else", ApiErrorKind::Other),
+        ];
+        for (message, kind) in cases {
+            assert_eq!(ApiErrorKind::from_message(message), kind, "{}", message);
+        }
+        let error = ApiError::new("/user/find", "user not found");
+        assert_eq!(error.to_string(), "/user/find: user not found");
+    }
+}
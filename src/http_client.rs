@@ -1,21 +1,30 @@
 use std::{
-    sync::Mutex,
-    thread,
+    collections::HashMap,
+    path::Path,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
     time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
-use reqwest::{Client, header::HeaderMap};
-use serde::{Serialize, de::DeserializeOwned};
+use bytes::Bytes;
+use reqwest::header::{CONTENT_TYPE, HeaderMap, HeaderValue, RETRY_AFTER};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
 use serde_json::json;
 
 use crate::{
+    cassette::{RecordingTransport, ReplayTransport},
     config::ScreepsConfig,
-    error::{ScreepsError, ScreepsResult},
-    model::TokenData,
+    error::{ApiError, ApiErrorKind, ScreepsError, ScreepsResult},
+    model::{BaseData, TokenData},
     rate_limit::RateLimits,
+    transport::{ReqwestTransport, Transport, TransportRequest},
 };
 
 /// 请求方法
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[serde(rename_all = "UPPERCASE")]
 pub enum Method {
     Get,
     Post,
@@ -26,67 +35,212 @@ pub type AnyPayload = Option<&'static [(&'static str, &'static str)]>;
 
 /// Screeps http 客户端
 pub struct ScreepsHttpClient {
-    /// 请求客户端
-    pub client: Client,
+    /// 传输层，默认使用 reqwest
+    pub transport: Box<dyn Transport>,
     /// 配置
     pub config: ScreepsConfig,
     /// 限速信息
     pub rate_limits: Mutex<RateLimits>,
     /// 最新的 token
     pub token: Mutex<Option<String>>,
+    /// 每个限速 key 的等待队列，保证同一接口的请求按顺序获得请求次数
+    rate_limit_queues: Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
+    /// 重新登录的锁，保证并发请求同时认证失败时只登录一次
+    auth_lock: tokio::sync::Mutex<()>,
+    /// 登录成功的次数，用于判断等待锁期间是否已经有其他请求重新登录
+    auth_generation: AtomicU64,
+    /// 当前时间戳（ms），用于计算限速等待时间，默认为系统时间，测试时可以替换
+    pub(crate) clock: Box<dyn Fn() -> u128 + Send + Sync>,
 }
 
 impl ScreepsHttpClient {
     pub fn new(config: ScreepsConfig) -> Self {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(config.timeout))
-            .build()
-            .unwrap();
+        let transport = ReqwestTransport::new(config.timeout);
+        Self::with_transport(config, transport)
+    }
 
+    /// 使用指定的传输层创建客户端，测试时可以传入 `MockTransport`
+    pub fn with_transport(config: ScreepsConfig, transport: impl Transport + 'static) -> Self {
         Self {
-            client,
+            transport: Box::new(transport),
             token: Mutex::new(config.token.clone()),
             config,
             rate_limits: Mutex::new(RateLimits::default()),
+            rate_limit_queues: Mutex::new(HashMap::new()),
+            auth_lock: tokio::sync::Mutex::new(()),
+            auth_generation: AtomicU64::new(0),
+            clock: Box::new(now_millis),
         }
     }
 
-    /// 封装 get 请求和 post 请求
+    /// 录制模式，请求真实服务器并记录所有请求和响应，通过返回的 `RecordingTransport` 保存录制内容
+    pub fn recording(config: ScreepsConfig) -> (Self, Arc<RecordingTransport>) {
+        let transport = Arc::new(RecordingTransport::new(ReqwestTransport::new(
+            config.timeout,
+        )));
+        (Self::with_transport(config, transport.clone()), transport)
+    }
+
+    /// 回放模式，从 cassette 文件返回录制的响应，不访问网络
+    pub fn replay(config: ScreepsConfig, path: impl AsRef<Path>) -> ScreepsResult<Self> {
+        Ok(Self::with_transport(config, ReplayTransport::load(path)?))
+    }
+
+    /// 封装 get 请求和 post 请求，按配置的重试策略重试网络错误、5xx 和 429 响应
+    ///
+    /// 配置了邮箱和密码时，认证失败会重新登录一次并重试请求
     pub async fn request<T: Serialize, U: DeserializeOwned>(
         &self,
         method: Method,
         path: &str,
         body: Option<T>,
     ) -> ScreepsResult<U> {
-        let url = self.build_url(path);
-        let request_builder = match method {
-            Method::Get => self.client.get(url).query(&body),
-            Method::Post => self.client.post(url).json(&body),
+        let retry = &self.config.retry;
+        let mut attempt = 1;
+        let mut reauthed = false;
+        let body = loop {
+            let generation = self.auth_generation.load(Ordering::SeqCst);
+            match self.send(&method, path, &body).await {
+                Ok(body) => break body,
+                Err(error) if !reauthed && self.should_reauth(path, &error) => {
+                    self.reauth(generation).await?;
+                    reauthed = true;
+                }
+                Err(error) if attempt < retry.max_attempts => {
+                    match retry_delay(&method, &error, retry.retry_post) {
+                        // 服务器要求等待的时间太长时直接返回错误，由调用方决定是否等待
+                        Some(Some(retry_after)) if retry_after > retry.max_delay => {
+                            return Err(error);
+                        }
+                        Some(retry_after) => {
+                            let delay = retry_after.unwrap_or_else(|| retry.backoff(attempt));
+                            tokio::time::sleep(delay).await;
+                            attempt += 1;
+                        }
+                        None => return Err(error),
+                    }
+                }
+                Err(error) => return Err(error),
+            }
+        };
+        let result = serde_json::from_slice::<U>(&body)?;
+        Ok(result)
+    }
+
+    /// 是否需要重新登录后重试
+    fn should_reauth(&self, path: &str, error: &ScreepsError) -> bool {
+        let auth_failed = match error {
+            ScreepsError::Status { status: 401, .. } => true,
+            ScreepsError::Api(error) => error.kind == ApiErrorKind::NotAuthorized,
+            _ => false,
+        };
+        auth_failed
+            && path != "/auth/signin"
+            && self.config.email.is_some()
+            && self.config.password.is_some()
+    }
+
+    /// 重新登录，如果等待期间其他请求已经登录成功则直接返回
+    /// 参数：
+    /// - generation: 发送失败请求前的登录次数
+    async fn reauth(&self, generation: u64) -> ScreepsResult<()> {
+        let _guard = self.auth_lock.lock().await;
+        if self.auth_generation.load(Ordering::SeqCst) != generation {
+            return Ok(());
         }
-        .headers(self.build_headers());
+        // auth 内部也会调用 request，需要 box 打破 async 递归
+        Box::pin(self.auth()).await.map(|_| ())
+    }
 
+    /// 发送一次请求，返回响应内容
+    async fn send<T: Serialize>(
+        &self,
+        method: &Method,
+        path: &str,
+        body: &Option<T>,
+    ) -> ScreepsResult<Bytes> {
         // 先检查速率限制
-        let rate_limit = self.rate_limits.lock().unwrap().get_limit(&method, path);
-        if rate_limit.remaining <= 0 {
-            let wait_time = rate_limit.reset * 1000
-                - SystemTime::now()
-                    .duration_since(UNIX_EPOCH)
-                    .unwrap()
-                    .as_millis() as u128;
-            if wait_time > 0 {
-                thread::sleep(Duration::from_millis(wait_time as u64));
+        self.wait_rate_limit(method, path).await;
+
+        let mut url = self.build_url(path);
+        let mut headers = self.build_headers();
+        let request_body = match method {
+            Method::Get => {
+                let query = serde_urlencoded::to_string(body)
+                    .map_err(|e| ScreepsError::InvalidParam(e.to_string()))?;
+                if !query.is_empty() {
+                    url = format!("{}?{}", url, query);
+                }
+                None
             }
-        }
-        let response = request_builder.send().await?;
-        if let Some(token) = response.headers().get("x-token") {
+            Method::Post => {
+                headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+                Some(Bytes::from(serde_json::to_vec(body)?))
+            }
+        };
+        let response = self
+            .transport
+            .send(TransportRequest {
+                method: method.clone(),
+                url,
+                headers,
+                body: request_body,
+            })
+            .await?;
+        if let Some(token) = response.headers.get("x-token") {
             *self.token.lock().unwrap() = Some(token.to_str().unwrap().to_string());
         }
         self.rate_limits
             .lock()
             .unwrap()
-            .update_from_headers(&method, path, response.headers());
-        let result = response.json::<U>().await?;
-        Ok(result)
+            .update_from_headers(method, path, &response.headers);
+
+        if response.status == 429 {
+            let retry_after = retry_after_from_headers(&response.headers, (self.clock)());
+            return Err(ScreepsError::RateLimited {
+                path: path.to_string(),
+                retry_after,
+            });
+        }
+        let body = response.body;
+        // 先检查状态码，带 JSON 错误内容的 5xx 响应也要返回 Status 以便重试
+        if !(200..300).contains(&response.status) {
+            return Err(ScreepsError::Status {
+                path: path.to_string(),
+                status: response.status,
+                body: String::from_utf8_lossy(&body).to_string(),
+            });
+        }
+        if self.config.check_ok {
+            check_ok(path, &body)?;
+        }
+        Ok(body)
+    }
+
+    /// 异步等待请求次数，次数用完时按排队顺序等到重置时间，不会阻塞线程
+    async fn wait_rate_limit(&self, method: &Method, path: &str) {
+        let queue = {
+            let key = self.rate_limits.lock().unwrap().limit_key(method, path);
+            self.rate_limit_queues
+                .lock()
+                .unwrap()
+                .entry(key)
+                .or_default()
+                .clone()
+        };
+        // tokio 的 Mutex 按加锁顺序唤醒等待者，同一接口的请求先到先得
+        let _guard = queue.lock().await;
+        loop {
+            let wait = self
+                .rate_limits
+                .lock()
+                .unwrap()
+                .try_acquire(method, path, (self.clock)());
+            match wait {
+                None => return,
+                Some(wait) => tokio::time::sleep(wait).await,
+            }
+        }
     }
 
     /// 构造请求头，添加 token
@@ -106,8 +260,69 @@ impl ScreepsHttpClient {
     }
 }
 
+/// 检查响应的 `ok` 字段，`ok` 不为 1 或者带有 `error` 时返回 `ScreepsError::Api`
+fn check_ok(path: &str, body: &[u8]) -> ScreepsResult<()> {
+    // 无法解析为 BaseData 的响应交给后续反序列化处理
+    let Ok(base_data) = serde_json::from_slice::<BaseData>(body) else {
+        return Ok(());
+    };
+    match (base_data.ok, base_data.error) {
+        (_, Some(error)) => Err(ScreepsError::Api(ApiError::new(path, &error))),
+        (Some(ok), None) if ok != 1 => Err(ScreepsError::Api(ApiError::new(
+            path,
+            &format!("ok: {}", ok),
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// 判断请求失败后是否可以重试，可以重试时返回服务器要求的等待时间（如果有）
+///
+/// 连接失败和 429 时服务器没有执行请求，总是可以重试；超时和 5xx 时服务器可能已经执行了请求，
+/// 只重试 GET 请求，POST 请求需要 `retry_post` 开启
+fn retry_delay(
+    method: &Method,
+    error: &ScreepsError,
+    retry_post: bool,
+) -> Option<Option<Duration>> {
+    let idempotent = *method == Method::Get || retry_post;
+    match error {
+        ScreepsError::Http(e) if e.is_connect() => Some(None),
+        ScreepsError::Http(e) if idempotent && (e.is_timeout() || e.is_request()) => Some(None),
+        ScreepsError::RateLimited { retry_after, .. } => Some(*retry_after),
+        ScreepsError::Status {
+            status: 500 | 502 | 503 | 504,
+            ..
+        } if idempotent => Some(None),
+        _ => None,
+    }
+}
+
+/// 从 429 响应头中读取需要等待的时间，优先使用 `Retry-After`，其次是 `x-ratelimit-reset`
+fn retry_after_from_headers(headers: &HeaderMap, now: u128) -> Option<Duration> {
+    let header = |name| {
+        headers
+            .get(name)
+            .and_then(|value: &reqwest::header::HeaderValue| value.to_str().ok())
+            .and_then(|value| value.trim().parse::<u128>().ok())
+    };
+    if let Some(seconds) = header(RETRY_AFTER.as_str()) {
+        return Some(Duration::from_secs(seconds as u64));
+    }
+    let reset = header("x-ratelimit-reset")? * 1000;
+    Some(Duration::from_millis(reset.saturating_sub(now) as u64))
+}
+
+/// 当前时间戳，单位 ms
+pub(crate) fn now_millis() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis()
+}
+
 impl ScreepsHttpClient {
-    /// 登录以获取 token
+    /// 登录以获取 token，登录成功后保存 token，账号或密码错误时返回 `ScreepsError::Auth`
     pub async fn auth(&self) -> ScreepsResult<TokenData> {
         if self.config.email.is_none() || self.config.password.is_none() {
             return Err(ScreepsError::Config(
@@ -125,13 +340,30 @@ impl ScreepsHttpClient {
                 })),
             )
             .await;
-        result
+        let data = match result {
+            Ok(data) => data,
+            Err(ScreepsError::Status { status: 401, .. }) => return Err(ScreepsError::Auth),
+            Err(ScreepsError::Api(error)) if error.kind == ApiErrorKind::NotAuthorized => {
+                return Err(ScreepsError::Auth);
+            }
+            Err(error) => return Err(error),
+        };
+        if let Some(token) = &data.token {
+            *self.token.lock().unwrap() = Some(token.clone());
+            self.auth_generation.fetch_add(1, Ordering::SeqCst);
+        }
+        Ok(data)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{
+        RetryPolicy, ShardTimeData,
+        rate_limit::{Period, RateLimit},
+        transport::{BoxFuture, MockResponse, MockTransport, TransportResponse},
+    };
     use std::env;
 
     #[test]
@@ -173,6 +405,318 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_check_ok() {
+        assert!(check_ok("/game/time", br#"{"ok":1,"time":1}"#).is_ok());
+        assert!(check_ok("/game/time", br#"{"time":1}"#).is_ok());
+        assert!(check_ok("/game/time", b"[1, 2]").is_ok());
+        match check_ok("/user/find", br#"{"error":"user not found"}"#) {
+            Err(ScreepsError::Api(error)) => {
+                assert_eq!(error.path, "/user/find");
+                assert_eq!(error.kind, crate::ApiErrorKind::UserNotFound);
+            }
+            result => panic!("unexpected result {:?}", result),
+        }
+        assert!(matches!(
+            check_ok("/user/code", br#"{"ok":0}"#),
+            Err(ScreepsError::Api(_))
+        ));
+    }
+
+    #[test]
+    fn test_retry_after_from_headers() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(retry_after_from_headers(&headers, 0), None);
+
+        headers.insert("x-ratelimit-reset", "100".parse().unwrap());
+        assert_eq!(
+            retry_after_from_headers(&headers, 90_000),
+            Some(Duration::from_secs(10))
+        );
+        assert_eq!(
+            retry_after_from_headers(&headers, 200_000),
+            Some(Duration::ZERO)
+        );
+
+        headers.insert(RETRY_AFTER, "3".parse().unwrap());
+        assert_eq!(
+            retry_after_from_headers(&headers, 90_000),
+            Some(Duration::from_secs(3))
+        );
+    }
+
+    #[test]
+    fn test_retry_delay() {
+        let rate_limited = ScreepsError::RateLimited {
+            path: "/game/room-objects".to_string(),
+            retry_after: Some(Duration::from_secs(1)),
+        };
+        assert_eq!(
+            retry_delay(&Get, &rate_limited, false),
+            Some(Some(Duration::from_secs(1)))
+        );
+        assert_eq!(
+            retry_delay(&Post, &rate_limited, false),
+            Some(Some(Duration::from_secs(1)))
+        );
+
+        let status = |status| ScreepsError::Status {
+            path: "/game/room-objects".to_string(),
+            status,
+            body: String::new(),
+        };
+        assert_eq!(retry_delay(&Get, &status(502), false), Some(None));
+        assert_eq!(retry_delay(&Get, &status(404), false), None);
+        assert_eq!(retry_delay(&Get, &ScreepsError::Auth, false), None);
+        // POST 遇到 5xx 时服务器可能已经执行了请求，默认不重试
+        assert_eq!(retry_delay(&Post, &status(502), false), None);
+        assert_eq!(retry_delay(&Post, &status(502), true), Some(None));
+    }
+
+    fn mock_client(transport: &Arc<MockTransport>) -> ScreepsHttpClient {
+        let mut config = ScreepsConfig::default();
+        config.with_token("token1".to_string());
+        config.with_retry(RetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(5),
+            jitter: false,
+            retry_post: false,
+        });
+        ScreepsHttpClient::with_transport(config, transport.clone())
+    }
+
+    #[tokio::test]
+    async fn test_token_rotation() {
+        let transport = Arc::new(MockTransport::new());
+        transport.on(
+            Get,
+            "/game/time",
+            MockResponse::json(&json!({ "ok": 1, "time": 1 })).header("x-token", "token2"),
+        );
+        let client = mock_client(&transport);
+
+        let _: ShardTimeData = client
+            .request(Get, "/game/time", AnyPayload::None)
+            .await
+            .unwrap();
+        assert_eq!(client.token.lock().unwrap().as_deref(), Some("token2"));
+        let _: ShardTimeData = client
+            .request(Get, "/game/time", AnyPayload::None)
+            .await
+            .unwrap();
+
+        let tokens: Vec<_> = transport
+            .requests()
+            .iter()
+            .map(|request| request.headers["X-Token"].to_str().unwrap().to_string())
+            .collect();
+        assert_eq!(tokens, vec!["token1", "token2"]);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_headers() {
+        let transport = Arc::new(MockTransport::new());
+        transport.on(
+            Get,
+            "/game/room-terrain",
+            MockResponse::json(&json!({ "ok": 1 })).rate_limit(360, 42, 1_700_000_000),
+        );
+        let client = mock_client(&transport);
+
+        let _: ShardTimeData = client
+            .request(Get, "/game/room-terrain", Some(&[("room", "E1N1")]))
+            .await
+            .unwrap();
+        let rate_limit = client
+            .rate_limits
+            .lock()
+            .unwrap()
+            .get_limit(&Get, "/game/room-terrain");
+        assert_eq!(rate_limit.remaining, 42);
+        assert_eq!(rate_limit.reset, 1_700_000_000);
+        assert_eq!(
+            transport.requests()[0].query("room").as_deref(),
+            Some("E1N1")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_retry_then_success() {
+        let transport = Arc::new(MockTransport::new());
+        transport
+            .on(Get, "/game/time", MockResponse::status(502))
+            .on(
+                Get,
+                "/game/time",
+                MockResponse::status(429).header("retry-after", "0"),
+            )
+            .on(
+                Get,
+                "/game/time",
+                MockResponse::json(&json!({ "ok": 1, "time": 7 })),
+            );
+        let client = mock_client(&transport);
+
+        let time: ShardTimeData = client
+            .request(Get, "/game/time", AnyPayload::None)
+            .await
+            .unwrap();
+        assert_eq!(time.time, Some(7));
+        assert_eq!(transport.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_error_status_with_json_body() {
+        let transport = Arc::new(MockTransport::new());
+        transport
+            .on(
+                Get,
+                "/game/time",
+                MockResponse::status(503).body(r#"{"error":"server busy"}"#),
+            )
+            .on(
+                Get,
+                "/game/time",
+                MockResponse::json(&json!({ "ok": 1, "time": 7 })),
+            );
+        let client = mock_client(&transport);
+
+        // 5xx 响应即使带有 error 字段也按状态码处理并重试
+        let time: ShardTimeData = client
+            .request(Get, "/game/time", AnyPayload::None)
+            .await
+            .unwrap();
+        assert_eq!(time.time, Some(7));
+        assert_eq!(transport.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_retry_gives_up() {
+        let transport = Arc::new(MockTransport::new());
+        transport.on(
+            Get,
+            "/game/time",
+            MockResponse::status(429).header("retry-after", "0"),
+        );
+        transport.on(
+            Post,
+            "/user/console",
+            MockResponse::status(404).body("not found"),
+        );
+        let client = mock_client(&transport);
+
+        let result: ScreepsResult<ShardTimeData> =
+            client.request(Get, "/game/time", AnyPayload::None).await;
+        assert!(matches!(result, Err(ScreepsError::RateLimited { .. })));
+        assert_eq!(transport.requests_to("/game/time").len(), 3);
+
+        let result: ScreepsResult<ShardTimeData> = client
+            .request(Post, "/user/console", Some(json!({ "expression": "1" })))
+            .await;
+        assert!(matches!(
+            result,
+            Err(ScreepsError::Status { status: 404, .. })
+        ));
+        assert_eq!(transport.requests_to("/user/console").len(), 1);
+        assert_eq!(
+            transport.requests_to("/user/console")[0].json(),
+            Some(json!({ "expression": "1" }))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_retry_skips_unsafe_post_and_long_waits() {
+        let transport = Arc::new(MockTransport::new());
+        transport.on(Post, "/game/market/create-order", MockResponse::status(502));
+        transport.on(
+            Get,
+            "/game/time",
+            MockResponse::status(429).header("retry-after", "60"),
+        );
+        let client = mock_client(&transport);
+
+        let result: ScreepsResult<ShardTimeData> = client
+            .request(
+                Post,
+                "/game/market/create-order",
+                Some(json!({ "price": 1 })),
+            )
+            .await;
+        assert!(matches!(
+            result,
+            Err(ScreepsError::Status { status: 502, .. })
+        ));
+        assert_eq!(transport.requests_to("/game/market/create-order").len(), 1);
+
+        // 等待时间超过 max_delay 时不睡眠，直接返回 RateLimited
+        let result: ScreepsResult<ShardTimeData> =
+            client.request(Get, "/game/time", AnyPayload::None).await;
+        assert!(matches!(
+            result,
+            Err(ScreepsError::RateLimited {
+                retry_after: Some(retry_after),
+                ..
+            }) if retry_after == Duration::from_secs(60)
+        ));
+        assert_eq!(transport.requests_to("/game/time").len(), 1);
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_wait_rate_limit_is_fair_and_non_blocking() {
+        // 暂停 tokio 时钟，限速使用的时间跟随 tokio 时钟，等待时间不受真实时间和并行测试影响
+        tokio::time::pause();
+        let start = tokio::time::Instant::now();
+        let base: u128 = 1_700_000_000_000;
+        let mut client = ScreepsHttpClient::new(ScreepsConfig::default());
+        client.clock = Box::new(move || base + start.elapsed().as_millis());
+        let client = Arc::new(client);
+        let path = "/game/room-terrain";
+        let reset = base / 1000 + 2;
+        client.rate_limits.lock().unwrap().update_limit(
+            &Method::Get,
+            path,
+            RateLimit::new(3, Period::Hour, 0, reset),
+        );
+
+        let order = Arc::new(Mutex::new(vec![]));
+        let mut waiters = vec![];
+        for i in 0..3 {
+            let client = client.clone();
+            let order = order.clone();
+            waiters.push(tokio::spawn(async move {
+                client.wait_rate_limit(&Method::Get, path).await;
+                order.lock().unwrap().push(i);
+            }));
+            tokio::task::yield_now().await;
+        }
+
+        // 等待期间运行时仍然可以调度其他任务
+        let ticks = tokio::spawn(async {
+            for _ in 0..5 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        });
+        ticks.await.unwrap();
+        assert!(order.lock().unwrap().is_empty());
+
+        for waiter in waiters {
+            waiter.await.unwrap();
+        }
+        assert_eq!(*order.lock().unwrap(), vec![0, 1, 2]);
+        // 所有请求都等到了重置时间
+        assert!(start.elapsed() >= Duration::from_secs(2));
+        assert_eq!(
+            client
+                .rate_limits
+                .lock()
+                .unwrap()
+                .get_limit(&Method::Get, path)
+                .remaining,
+            0
+        );
+    }
+
     #[tokio::test]
     async fn test_auth() {
         let _ = dotenvy::dotenv();
@@ -221,4 +765,152 @@ mod tests {
             }
         }
     }
+
+    fn mock_client_with_credentials(transport: impl Transport + 'static) -> ScreepsHttpClient {
+        let mut config = ScreepsConfig::default();
+        config.with_token("expired".to_string());
+        config.with_email("email".to_string());
+        config.with_password("password".to_string());
+        config.with_retry(RetryPolicy::none());
+        ScreepsHttpClient::with_transport(config, transport)
+    }
+
+    #[tokio::test]
+    async fn test_reauth_on_unauthorized() {
+        let transport = Arc::new(MockTransport::new());
+        transport
+            .on(
+                Get,
+                "/game/time",
+                MockResponse::status(401).body("Unauthorized"),
+            )
+            .on(
+                Get,
+                "/game/time",
+                MockResponse::json(&json!({ "ok": 1, "time": 1 })),
+            )
+            .on(
+                Post,
+                "/auth/signin",
+                MockResponse::json(&json!({ "ok": 1, "token": "fresh" })),
+            );
+        let client = mock_client_with_credentials(transport.clone());
+
+        let data: ShardTimeData = client
+            .request(Get, "/game/time", AnyPayload::None)
+            .await
+            .unwrap();
+        assert_eq!(data.time, Some(1));
+        assert_eq!(transport.requests_to("/auth/signin").len(), 1);
+        let tokens: Vec<_> = transport
+            .requests_to("/game/time")
+            .iter()
+            .map(|request| request.headers["X-Token"].to_str().unwrap().to_string())
+            .collect();
+        assert_eq!(tokens, vec!["expired", "fresh"]);
+    }
+
+    #[tokio::test]
+    async fn test_reauth_only_once_per_request() {
+        let transport = Arc::new(MockTransport::new());
+        transport
+            .on(
+                Get,
+                "/game/time",
+                MockResponse::json(&json!({ "error": "not authorized" })),
+            )
+            .on(
+                Post,
+                "/auth/signin",
+                MockResponse::json(&json!({ "ok": 1, "token": "fresh" })),
+            );
+        let client = mock_client_with_credentials(transport.clone());
+
+        let result: ScreepsResult<ShardTimeData> =
+            client.request(Get, "/game/time", AnyPayload::None).await;
+        match result {
+            Err(ScreepsError::Api(error)) => assert_eq!(error.kind, ApiErrorKind::NotAuthorized),
+            result => panic!("unexpected result {:?}", result.map(|_| ())),
+        }
+        assert_eq!(transport.requests_to("/game/time").len(), 2);
+        assert_eq!(transport.requests_to("/auth/signin").len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_auth_failed() {
+        let transport = Arc::new(MockTransport::new());
+        transport.on(Post, "/auth/signin", MockResponse::status(401));
+        let client = mock_client_with_credentials(transport.clone());
+        assert!(matches!(client.auth().await, Err(ScreepsError::Auth)));
+        assert_eq!(client.token.lock().unwrap().as_deref(), Some("expired"));
+    }
+
+    #[tokio::test]
+    async fn test_no_reauth_without_credentials() {
+        let transport = Arc::new(MockTransport::new());
+        transport.on(Get, "/game/time", MockResponse::status(401));
+        let client = mock_client(&transport);
+        let result: ScreepsResult<ShardTimeData> =
+            client.request(Get, "/game/time", AnyPayload::None).await;
+        assert!(matches!(
+            result,
+            Err(ScreepsError::Status { status: 401, .. })
+        ));
+        assert!(transport.requests_to("/auth/signin").is_empty());
+    }
+
+    /// 只接受最新 token 的传输层，登录时会等待一段时间，便于并发请求同时认证失败
+    #[derive(Default)]
+    struct TokenCheckingTransport {
+        signins: AtomicU64,
+    }
+
+    impl Transport for TokenCheckingTransport {
+        fn send(
+            &self,
+            request: TransportRequest,
+        ) -> BoxFuture<'_, ScreepsResult<TransportResponse>> {
+            Box::pin(async move {
+                let response = if request.path() == "/auth/signin" {
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                    let signins = self.signins.fetch_add(1, Ordering::SeqCst) + 1;
+                    MockResponse::json(&json!({ "ok": 1, "token": format!("token{}", signins) }))
+                } else {
+                    let valid = format!("token{}", self.signins.load(Ordering::SeqCst));
+                    if request.headers["X-Token"] == valid.as_str() {
+                        MockResponse::json(&json!({ "ok": 1, "time": 1 }))
+                    } else {
+                        MockResponse::status(401)
+                    }
+                };
+                Ok(TransportResponse {
+                    status: response.status,
+                    headers: response.headers,
+                    body: response.body,
+                })
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_reauth_single_flight() {
+        let transport = Arc::new(TokenCheckingTransport::default());
+        let client = Arc::new(mock_client_with_credentials(transport.clone()));
+
+        let tasks: Vec<_> = (0..5)
+            .map(|_| {
+                let client = client.clone();
+                tokio::spawn(async move {
+                    client
+                        .request::<_, ShardTimeData>(Get, "/game/time", AnyPayload::None)
+                        .await
+                })
+            })
+            .collect();
+        for task in tasks {
+            assert_eq!(task.await.unwrap().unwrap().time, Some(1));
+        }
+        assert_eq!(transport.signins.load(Ordering::SeqCst), 1);
+        assert_eq!(client.token.lock().unwrap().as_deref(), Some("token1"));
+    }
 }
@@ -0,0 +1,226 @@
+//! 玩家资源汇总
+//!
+//! `get_user_inventory` 查询玩家所有房间的对象，按 shard、房间和对象汇总资源。
+
+use std::collections::BTreeMap;
+
+use crate::{
+    error::ScreepsError,
+    resource::Store,
+    room_name::RoomName,
+    types::{BaseObject, RoomObject},
+};
+
+/// 单个对象中的资源
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjectInventory {
+    pub id: String,
+    /// 对象类型，与 `type` 字段相同，例如 `storage`、`creep`
+    pub object_type: String,
+    /// creep 和 power creep 的名称
+    pub name: Option<String>,
+    pub x: i32,
+    pub y: i32,
+    pub store: Store,
+}
+
+impl ObjectInventory {
+    fn new(object_type: &str, base: &BaseObject, name: Option<&str>, store: &Store) -> Self {
+        Self {
+            id: base._id.clone(),
+            object_type: object_type.to_string(),
+            name: name.map(str::to_string),
+            x: base.x,
+            y: base.y,
+            store: store.clone(),
+        }
+    }
+}
+
+/// 单个房间的资源
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoomInventory {
+    pub room: RoomName,
+    pub total: Store,
+    /// 有资源的对象
+    pub objects: Vec<ObjectInventory>,
+}
+
+impl RoomInventory {
+    /// 汇总房间内建筑和指定玩家的 creep、power creep 中的资源
+    ///
+    /// 参数：
+    /// - room: 房间名
+    /// - objects: 房间内的对象，可以通过 `get_room_objects` 获取
+    /// - user_id: 玩家 id，其他玩家的 creep 不会计入
+    pub fn from_objects(room: RoomName, objects: &[RoomObject], user_id: &str) -> Self {
+        let mut inventory = Self {
+            room,
+            total: Store::new(),
+            objects: Vec::new(),
+        };
+        for object in objects {
+            let object = match object {
+                RoomObject::Spawn(o) => {
+                    ObjectInventory::new("spawn", &o.base_object, None, &o.store)
+                }
+                RoomObject::Extension(o) => {
+                    ObjectInventory::new("extension", &o.base_object, None, &o.store)
+                }
+                RoomObject::Storage(o) => {
+                    ObjectInventory::new("storage", &o.base_object, None, &o.store)
+                }
+                RoomObject::Tower(o) => {
+                    ObjectInventory::new("tower", &o.base_object, None, &o.store)
+                }
+                RoomObject::Terminal(o) => {
+                    ObjectInventory::new("terminal", &o.base_object, None, &o.store)
+                }
+                RoomObject::PowerSpawn(o) => {
+                    ObjectInventory::new("powerSpawn", &o.base_object, None, &o.store)
+                }
+                RoomObject::Nuker(o) => {
+                    ObjectInventory::new("nuker", &o.base_object, None, &o.store)
+                }
+                RoomObject::Factory(o) => {
+                    ObjectInventory::new("factory", &o.base_object, None, &o.store)
+                }
+                RoomObject::Lab(o) => ObjectInventory::new("lab", &o.base_object, None, &o.store),
+                RoomObject::Link(o) => ObjectInventory::new("link", &o.base_object, None, &o.store),
+                RoomObject::Container(o) => {
+                    ObjectInventory::new("container", &o.base_object, None, &o.store)
+                }
+                RoomObject::Creep(o) if o.user == user_id => {
+                    ObjectInventory::new("creep", &o.base_object, Some(&o.name), &o.store)
+                }
+                RoomObject::PowerCreep(o) if o.user == user_id => {
+                    ObjectInventory::new("powerCreep", &o.base_object, Some(&o.name), &o.store)
+                }
+                _ => continue,
+            };
+            if object.store.total() == 0 {
+                continue;
+            }
+            inventory.total.merge(&object.store);
+            inventory.objects.push(object);
+        }
+        inventory
+    }
+
+    /// 按对象类型汇总资源
+    pub fn by_type(&self) -> BTreeMap<String, Store> {
+        let mut by_type = BTreeMap::<String, Store>::new();
+        for object in &self.objects {
+            by_type
+                .entry(object.object_type.clone())
+                .or_default()
+                .merge(&object.store);
+        }
+        by_type
+    }
+}
+
+/// 单个 shard 的资源
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ShardInventory {
+    pub total: Store,
+    pub rooms: BTreeMap<RoomName, RoomInventory>,
+}
+
+impl ShardInventory {
+    /// 按对象类型汇总资源
+    pub fn by_type(&self) -> BTreeMap<String, Store> {
+        let mut by_type = BTreeMap::<String, Store>::new();
+        for room in self.rooms.values() {
+            for (object_type, store) in room.by_type() {
+                *by_type.entry(object_type).or_default() += store;
+            }
+        }
+        by_type
+    }
+}
+
+/// 查询失败的房间
+#[derive(Debug)]
+pub struct InventoryFailure {
+    pub shard: String,
+    pub room: String,
+    pub error: ScreepsError,
+}
+
+/// `get_user_inventory` 的结果
+#[derive(Debug, Default)]
+pub struct UserInventory {
+    pub user_id: String,
+    pub username: String,
+    /// 所有 shard 的资源总和
+    pub total: Store,
+    pub shards: BTreeMap<String, ShardInventory>,
+    /// 查询失败的房间，这些房间的资源不计入总和
+    pub failed: Vec<InventoryFailure>,
+}
+
+impl UserInventory {
+    /// 是否所有房间都查询成功
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_empty()
+    }
+
+    /// 加入一个房间的资源
+    pub(crate) fn add_room(&mut self, shard: &str, room: RoomInventory) {
+        self.total.merge(&room.total);
+        let shard = self.shards.entry(shard.to_string()).or_default();
+        shard.total.merge(&room.total);
+        shard.rooms.insert(room.room, room);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resource::ResourceType;
+
+    #[test]
+    fn test_room_inventory() {
+        let objects: Vec<RoomObject> = serde_json::from_str(
+            r#"[
+            { "_id": "s1", "type": "storage", "room": "E13S13", "x": 10, "y": 10, "hits": 10000,
+              "hitsMax": 10000, "user": "u1", "notifyWhenAttacked": true,
+              "store": { "energy": 100000, "XGH2O": 3000 }, "storeCapacity": 1000000 },
+            { "_id": "c1", "type": "container", "room": "E13S13", "x": 5, "y": 5, "hits": 250000,
+              "hitsMax": 250000, "store": { "energy": 1500 } },
+            { "_id": "c2", "type": "container", "room": "E13S13", "x": 6, "y": 5, "hits": 250000,
+              "hitsMax": 250000, "store": {} },
+            { "_id": "cr1", "type": "creep", "room": "E13S13", "x": 7, "y": 7, "name": "hauler",
+              "hits": 100, "hitsMax": 100, "user": "u1", "store": { "energy": 50 } },
+            { "_id": "cr2", "type": "creep", "room": "E13S13", "x": 8, "y": 7, "name": "thief",
+              "hits": 100, "hitsMax": 100, "user": "u2", "store": { "energy": 50 } }
+        ]"#,
+        )
+        .unwrap();
+        let room = RoomInventory::from_objects("E13S13".parse().unwrap(), &objects, "u1");
+        assert_eq!(room.objects.len(), 3);
+        assert_eq!(room.total.get(ResourceType::Energy), 101_550);
+        assert_eq!(room.total.get(ResourceType::CatalyzedGhodiumAcid), 3000);
+        let by_type = room.by_type();
+        assert_eq!(by_type["container"].get(ResourceType::Energy), 1500);
+        assert_eq!(by_type["creep"].get(ResourceType::Energy), 50);
+        assert_eq!(room.objects[2].name.as_deref(), Some("hauler"));
+
+        let mut inventory = UserInventory::default();
+        inventory.add_room("shard3", room.clone());
+        let other = RoomInventory {
+            room: "E14S13".parse().unwrap(),
+            ..room
+        };
+        inventory.add_room("shard3", other);
+        assert!(inventory.is_complete());
+        assert_eq!(inventory.total.get(ResourceType::Energy), 203_100);
+        let shard = &inventory.shards["shard3"];
+        assert_eq!(shard.rooms.len(), 2);
+        assert_eq!(
+            shard.by_type()["storage"].get(ResourceType::Energy),
+            200_000
+        );
+    }
+}
@@ -1,16 +1,44 @@
 pub mod api;
+pub mod cassette;
 pub mod config;
+pub mod creep;
+pub mod defense;
 pub mod error;
 pub mod http_client;
+pub mod inventory;
+#[cfg(feature = "mock-server")]
+pub mod mock_server;
 pub mod model;
+pub mod progress;
 pub mod rate_limit;
+pub mod resource;
+pub mod room_name;
+pub mod room_state;
+pub mod socket;
+pub mod terrain;
+pub mod terrain_cache;
+pub mod transport;
 pub mod types;
 
 pub mod utils;
 
 pub use api::*;
+pub use cassette::*;
 pub use config::*;
+pub use creep::*;
+pub use defense::*;
 pub use error::*;
 pub use http_client::*;
+pub use inventory::*;
+#[cfg(feature = "mock-server")]
+pub use mock_server::*;
 pub use model::*;
+pub use progress::*;
+pub use resource::*;
+pub use room_name::*;
+pub use room_state::*;
+pub use socket::*;
+pub use terrain::*;
+pub use terrain_cache::*;
+pub use transport::*;
 pub use types::*;
@@ -0,0 +1,521 @@
+//! 本地模拟 Screeps 服务器，需要开启 `mock-server` feature
+//!
+//! 从 json 夹具返回数据，并像官方服务器一样返回 `x-token` 和 `x-ratelimit-*` 响应头，
+//! 用于离线运行集成测试：
+//!
+//! ```no_run
+//! # async fn run() -> std::io::Result<()> {
+//! use screeps_rust_api::{ScreepsApi, mock_server::MockServer};
+//!
+//! let server = MockServer::start().await?;
+//! let api = ScreepsApi::new(server.config());
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    collections::{HashMap, HashSet},
+    io,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use axum::{
+    Router,
+    body::Bytes,
+    extract::State,
+    http::{HeaderMap, HeaderValue, Method as HttpMethod, StatusCode, Uri},
+    response::{IntoResponse, Response},
+};
+use serde_json::{Value, json};
+use tokio::{net::TcpListener, task::JoinHandle};
+
+use crate::{
+    config::ScreepsConfig,
+    http_client::Method,
+    rate_limit::{Period, RateLimits},
+};
+
+/// 夹具对应的接口路径和内容
+const DEFAULT_FIXTURES: [(&str, &str); 8] = [
+    (
+        "/auth/me",
+        include_str!("../fixtures/mock_server/auth_me.json"),
+    ),
+    (
+        "/user/find",
+        include_str!("../fixtures/mock_server/user_find.json"),
+    ),
+    (
+        "/user/rooms",
+        include_str!("../fixtures/mock_server/user_rooms.json"),
+    ),
+    (
+        "/game/room-objects",
+        include_str!("../fixtures/mock_server/room_objects.json"),
+    ),
+    (
+        "/game/room-terrain",
+        include_str!("../fixtures/mock_server/room_terrain.json"),
+    ),
+    (
+        "/game/room-status",
+        include_str!("../fixtures/mock_server/room_status.json"),
+    ),
+    (
+        "/game/shards/info",
+        include_str!("../fixtures/mock_server/shards_info.json"),
+    ),
+    (
+        "/game/time",
+        include_str!("../fixtures/mock_server/game_time.json"),
+    ),
+];
+
+/// 需要 token 才能访问的接口
+const AUTH_REQUIRED: [&str; 2] = ["/auth/me", "/game/room-status"];
+
+/// 模拟服务器接受的登录邮箱
+pub const MOCK_EMAIL: &str = "test@example.com";
+/// 模拟服务器接受的登录密码
+pub const MOCK_PASSWORD: &str = "password";
+
+/// 模拟服务器的状态
+struct ServerState {
+    /// 接口路径到夹具内容的映射
+    fixtures: HashMap<String, Value>,
+    /// 有效的 token
+    tokens: HashSet<String>,
+    /// 已签发的 token 数量，用于生成新 token
+    issued: u64,
+    /// 每个限速 key 的剩余次数和重置时间
+    windows: HashMap<String, (i32, u64)>,
+    rate_limits: RateLimits,
+}
+
+impl ServerState {
+    /// 签发新的 token
+    fn issue_token(&mut self) -> String {
+        self.issued += 1;
+        let token = format!("mock-token-{}", self.issued);
+        self.tokens.insert(token.clone());
+        token
+    }
+
+    /// 扣减一次请求次数，返回限制次数、剩余次数和重置时间，次数用完时剩余次数为负数
+    fn consume(&mut self, method: &Method, path: &str) -> (i32, i32, u64) {
+        let key = self.rate_limits.limit_key(method, path);
+        let rate_limit = self.rate_limits.get_limit(method, path);
+        let period = match rate_limit.period {
+            Period::Minute => 60,
+            Period::Hour => 3600,
+            Period::Day => 86400,
+        };
+        let now = now_secs();
+        let window = self
+            .windows
+            .entry(key)
+            .or_insert((rate_limit.limit, now + period));
+        if now >= window.1 {
+            *window = (rate_limit.limit, now + period);
+        }
+        window.0 -= 1;
+        (rate_limit.limit, window.0, window.1)
+    }
+}
+
+/// 本地模拟服务器，被 drop 时停止
+pub struct MockServer {
+    addr: SocketAddr,
+    state: Arc<Mutex<ServerState>>,
+    task: JoinHandle<()>,
+}
+
+impl MockServer {
+    /// 使用内置夹具在随机端口启动服务器
+    pub async fn start() -> io::Result<Self> {
+        Self::start_with_fixtures(HashMap::new()).await
+    }
+
+    /// 启动服务器，`fixtures` 中的内容会覆盖同路径的内置夹具
+    /// 参数：
+    /// - fixtures: 接口路径（不含 `/api` 前缀）到响应内容的映射
+    pub async fn start_with_fixtures(fixtures: HashMap<String, Value>) -> io::Result<Self> {
+        let mut all_fixtures: HashMap<String, Value> = DEFAULT_FIXTURES
+            .iter()
+            .map(|(path, fixture)| {
+                (
+                    path.to_string(),
+                    serde_json::from_str(fixture).expect("invalid fixture"),
+                )
+            })
+            .collect();
+        all_fixtures.extend(fixtures);
+
+        let state = Arc::new(Mutex::new(ServerState {
+            fixtures: all_fixtures,
+            tokens: HashSet::new(),
+            issued: 0,
+            windows: HashMap::new(),
+            rate_limits: RateLimits::default(),
+        }));
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let app = Router::new().fallback(handle).with_state(state.clone());
+        let task = tokio::spawn(async move {
+            let _ = axum::serve(listener, app).await;
+        });
+        Ok(Self { addr, state, task })
+    }
+
+    /// 服务器地址
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// 连接本服务器的配置，不带 token
+    pub fn config(&self) -> ScreepsConfig {
+        let mut config = ScreepsConfig::default();
+        config.with_host(self.addr.to_string());
+        config.with_secure(false);
+        config
+    }
+
+    /// 签发一个有效的 token
+    pub fn issue_token(&self) -> String {
+        self.state.lock().unwrap().issue_token()
+    }
+
+    /// 让所有已签发的 token 失效
+    pub fn expire_tokens(&self) {
+        self.state.lock().unwrap().tokens.clear();
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// 处理所有请求
+async fn handle(
+    State(state): State<Arc<Mutex<ServerState>>>,
+    method: HttpMethod,
+    uri: Uri,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let Some(path) = uri.path().strip_prefix("/api") else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let method = if method == HttpMethod::POST {
+        Method::Post
+    } else {
+        Method::Get
+    };
+    let query: HashMap<String, String> = uri
+        .query()
+        .map(|query| serde_urlencoded::from_str(query).unwrap_or_default())
+        .unwrap_or_default();
+
+    let mut state = state.lock().unwrap();
+    let (limit, remaining, reset) = state.consume(&method, path);
+    let mut response_headers = HeaderMap::new();
+    response_headers.insert("x-ratelimit-limit", HeaderValue::from(limit));
+    response_headers.insert("x-ratelimit-remaining", HeaderValue::from(remaining.max(0)));
+    response_headers.insert("x-ratelimit-reset", HeaderValue::from(reset));
+    if remaining < 0 {
+        let retry_after = reset.saturating_sub(now_secs());
+        response_headers.insert("retry-after", HeaderValue::from(retry_after));
+        return (
+            StatusCode::TOO_MANY_REQUESTS,
+            response_headers,
+            "Rate limit exceeded",
+        )
+            .into_response();
+    }
+
+    // 带有有效 token 的请求会拿到新 token，和官方服务器一样旧 token 仍然有效
+    let token = headers.get("x-token").and_then(|t| t.to_str().ok());
+    let authed = token.is_some_and(|token| state.tokens.contains(token));
+    if authed {
+        let token = state.issue_token();
+        response_headers.insert("x-token", HeaderValue::from_str(&token).unwrap());
+    } else if AUTH_REQUIRED.contains(&path) {
+        return (StatusCode::UNAUTHORIZED, response_headers, "Unauthorized").into_response();
+    }
+
+    let body = match (&method, path) {
+        (Method::Post, "/auth/signin") => {
+            let credentials: Value = serde_json::from_slice(&body).unwrap_or_default();
+            if credentials["email"] != MOCK_EMAIL || credentials["password"] != MOCK_PASSWORD {
+                return (StatusCode::UNAUTHORIZED, response_headers, "Unauthorized")
+                    .into_response();
+            }
+            json!({ "ok": 1, "token": state.issue_token() })
+        }
+        (Method::Get, "/user/find") => {
+            let fixture = &state.fixtures[path];
+            let user = &fixture["user"];
+            let matched = query
+                .get("username")
+                .is_some_and(|name| user["username"] == *name)
+                || query.get("id").is_some_and(|id| user["_id"] == *id);
+            if matched {
+                fixture.clone()
+            } else {
+                json!({ "error": "user not found" })
+            }
+        }
+        (Method::Get, "/user/rooms") => {
+            let user_id = &state.fixtures["/user/find"]["user"]["_id"];
+            if query.get("id").is_some_and(|id| user_id == id) {
+                state.fixtures[path].clone()
+            } else {
+                json!({ "ok": 1, "shards": {}, "reservations": {} })
+            }
+        }
+        (Method::Get, "/game/room-objects") => {
+            let Some(room) = room_param(&query) else {
+                return invalid_room(response_headers);
+            };
+            let fixture = &state.fixtures[path];
+            let matched = fixture["objects"]
+                .as_array()
+                .is_some_and(|objects| objects.iter().any(|object| object["room"] == room));
+            if matched {
+                fixture.clone()
+            } else {
+                json!({ "ok": 1, "objects": [], "users": {} })
+            }
+        }
+        (Method::Get, "/game/room-terrain") => {
+            let Some(room) = room_param(&query) else {
+                return invalid_room(response_headers);
+            };
+            room_terrain(&state.fixtures[path], room, query.contains_key("encoded"))
+        }
+        (Method::Get, "/game/room-status") => {
+            if room_param(&query).is_none() {
+                return invalid_room(response_headers);
+            }
+            state.fixtures[path].clone()
+        }
+        _ => match state.fixtures.get(path) {
+            Some(fixture) => fixture.clone(),
+            None => return (StatusCode::NOT_FOUND, response_headers).into_response(),
+        },
+    };
+    (response_headers, axum::Json(body)).into_response()
+}
+
+/// 读取并校验 room 参数
+fn room_param(query: &HashMap<String, String>) -> Option<&str> {
+    let room = query.get("room")?;
+    let mut parts = room
+        .split(|c: char| c.is_ascii_digit())
+        .filter(|s| !s.is_empty());
+    let valid = matches!(
+        (parts.next(), parts.next(), parts.next()),
+        (Some("E" | "W"), Some("N" | "S"), None)
+    ) && room.chars().any(|c| c.is_ascii_digit());
+    valid.then_some(room.as_str())
+}
+
+/// 房间名不合法的响应
+fn invalid_room(headers: HeaderMap) -> Response {
+    (headers, axum::Json(json!({ "error": "invalid room" }))).into_response()
+}
+
+/// 根据编码后的地形夹具构造地形响应，非编码格式只返回沼泽和墙
+fn room_terrain(fixture: &Value, room: &str, encoded: bool) -> Value {
+    let Some(terrain) = fixture["terrain"]
+        .as_array()
+        .and_then(|list| list.iter().find(|terrain| terrain["room"] == room))
+    else {
+        return json!({ "ok": 1, "terrain": [] });
+    };
+    if encoded {
+        return json!({ "ok": 1, "terrain": [terrain] });
+    }
+    let tiles: Vec<Value> = terrain["terrain"]
+        .as_str()
+        .unwrap_or_default()
+        .chars()
+        .enumerate()
+        .filter_map(|(i, c)| {
+            let r#type = match c {
+                '1' | '3' => "wall",
+                '2' => "swamp",
+                _ => return None,
+            };
+            Some(json!({ "room": room, "x": i % 50, "y": i / 50, "type": r#type }))
+        })
+        .collect();
+    json!({ "ok": 1, "terrain": tiles })
+}
+
+/// 当前时间戳，单位 s
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{RoomObject, ScreepsApi, ScreepsError, ScreepsResult};
+
+    async fn mock_api() -> (MockServer, ScreepsApi) {
+        let server = MockServer::start().await.unwrap();
+        let mut config = server.config();
+        config.with_token(server.issue_token());
+        let api = ScreepsApi::new(config);
+        (server, api)
+    }
+
+    #[tokio::test]
+    async fn test_signin() {
+        let server = MockServer::start().await.unwrap();
+        let mut config = server.config();
+        config.with_email(MOCK_EMAIL.to_string());
+        config.with_password(MOCK_PASSWORD.to_string());
+        let api = ScreepsApi::new(config);
+        let token = api.auth().await.unwrap().token.unwrap();
+        assert!(token.starts_with("mock-token-"));
+
+        let mut config = server.config();
+        config.with_email(MOCK_EMAIL.to_string());
+        config.with_password("wrong".to_string());
+        let result = ScreepsApi::new(config).auth().await;
+        assert!(matches!(result, Err(ScreepsError::Auth)));
+    }
+
+    #[tokio::test]
+    async fn test_user_endpoints() {
+        let (_server, api) = mock_api().await;
+        let my_info = api.get_my_info().await.unwrap();
+        assert_eq!(my_info.user.unwrap().username, "keqing");
+        let token = api.http_client.token.lock().unwrap().clone().unwrap();
+        assert!(token.starts_with("mock-token-"));
+
+        let user = api.get_user_info_by_name("keqing").await.unwrap();
+        assert_eq!(user.user.unwrap()._id, "61f26f882181b7ba48c7015c");
+        let rooms = api
+            .get_user_rooms("61f26f882181b7ba48c7015c")
+            .await
+            .unwrap();
+        assert_eq!(rooms.shards.unwrap()["shard3"], vec!["E13S13"]);
+
+        let result = api.get_user_info_by_name("nobody").await;
+        assert!(matches!(result, Err(ScreepsError::Api(_))));
+    }
+
+    #[tokio::test]
+    async fn test_unauthorized() {
+        let (server, api) = mock_api().await;
+        server.expire_tokens();
+        let result = api.get_my_info().await;
+        assert!(matches!(
+            result,
+            Err(ScreepsError::Status { status: 401, .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_reauth_after_token_expired() {
+        let (server, _) = mock_api().await;
+        let mut config = server.config();
+        config.with_token(server.issue_token());
+        config.with_email(MOCK_EMAIL.to_string());
+        config.with_password(MOCK_PASSWORD.to_string());
+        let api = ScreepsApi::new(config);
+        server.expire_tokens();
+        let my_info = api.get_my_info().await.unwrap();
+        assert_eq!(my_info.user.unwrap().username, "keqing");
+    }
+
+    #[tokio::test]
+    async fn test_room_endpoints() {
+        let (_server, api) = mock_api().await;
+        let objects = api.get_room_objects("E13S13", "shard3").await.unwrap();
+        let objects = objects.objects.unwrap();
+        assert!(
+            objects
+                .iter()
+                .any(|o| matches!(o, RoomObject::Controller(_)))
+        );
+        assert!(objects.iter().all(|o| !matches!(o, RoomObject::Unknown(_))));
+
+        let encoded = api
+            .get_room_terrain_encoded("E13S13", "shard3")
+            .await
+            .unwrap();
+        let encoded = &encoded.terrain.unwrap()[0].terrain;
+        assert_eq!(encoded.len(), 2500);
+        let terrain = api.get_room_terrain("E13S13", "shard3").await.unwrap();
+        let walls_and_swamps = encoded.chars().filter(|c| *c != '0').count();
+        assert_eq!(terrain.terrain.unwrap().len(), walls_and_swamps);
+
+        let status = api.get_room_status("E13S13", "shard3").await.unwrap();
+        assert_eq!(status.rooms.unwrap().status, "normal");
+
+        let result = api.get_room_objects("nowhere", "shard3").await;
+        assert!(matches!(result, Err(ScreepsError::InvalidParam(_))));
+        let result: ScreepsResult<Value> = api
+            .request(
+                Method::Get,
+                "/game/room-objects",
+                Some(&[("room", "nowhere"), ("shard", "shard3")]),
+            )
+            .await;
+        assert!(matches!(result, Err(ScreepsError::Api(_))));
+    }
+
+    #[tokio::test]
+    async fn test_game_endpoints() {
+        let (_server, api) = mock_api().await;
+        let shards = api.get_shards().await.unwrap();
+        assert_eq!(shards.shards.unwrap().len(), 2);
+        let time = api.get_shard_time("shard3").await.unwrap();
+        assert_eq!(time.time, Some(62000000));
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_headers() {
+        let (_server, api) = mock_api().await;
+        api.get_room_terrain("E13S13", "shard3").await.unwrap();
+        let rate_limit = api
+            .http_client
+            .rate_limits
+            .lock()
+            .unwrap()
+            .get_limit(&Method::Get, "/game/room-terrain");
+        assert_eq!(rate_limit.limit, 360);
+        assert_eq!(rate_limit.remaining, 359);
+        assert!(rate_limit.reset > now_secs() as u128);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited() {
+        let server = MockServer::start().await.unwrap();
+        {
+            let mut state = server.state.lock().unwrap();
+            let key = state.rate_limits.limit_key(&Method::Get, "/game/time");
+            state.windows.insert(key, (0, now_secs() + 30));
+        }
+        let mut config = server.config();
+        config.with_retry(crate::RetryPolicy::none());
+        let result = ScreepsApi::new(config).get_shard_time("shard3").await;
+        match result {
+            Err(ScreepsError::RateLimited { retry_after, .. }) => {
+                assert!(retry_after.unwrap().as_secs() <= 30);
+            }
+            result => panic!("unexpected result {:?}", result.map(|_| ())),
+        }
+    }
+}
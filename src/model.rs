@@ -1,9 +1,12 @@
 use std::collections::HashMap;
 
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 use crate::{
-    EncodedRoomTerrain, MyInfo, RoomStatus, RoomTerrain, ShardInfo, UserInfo,
+    Branch, CodeModule, ConsoleResult, EncodedRoomTerrain, MapRoomStat, MarketOrder,
+    MarketOrderIndex, MarketStat, MoneyHistoryEntry, MyInfo, RoomStatus, RoomTerrain, ShardInfo,
+    UserInfo,
     types::{RoomObject, UserWithId},
 };
 
@@ -16,6 +19,13 @@ pub struct BaseData {
     pub error: Option<String>,
 }
 
+/// 只包含基础数据的响应，用于没有额外返回值的接口
+#[derive(Serialize, Deserialize, Debug)]
+pub struct OkData {
+    #[serde(flatten)]
+    pub base_data: BaseData,
+}
+
 /// auth 认证返回 token 数据
 #[derive(Serialize, Deserialize, Debug)]
 pub struct TokenData {
@@ -109,3 +119,114 @@ pub struct ShardTimeData {
     pub base_data: BaseData,
     pub time: Option<u64>,
 }
+
+/// 代码数据
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CodeData {
+    #[serde(flatten)]
+    pub base_data: BaseData,
+    /// 分支名
+    pub branch: Option<String>,
+    /// 模块名到模块内容的映射
+    pub modules: Option<HashMap<String, CodeModule>>,
+}
+
+/// 上传代码结果
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SetCodeData {
+    #[serde(flatten)]
+    pub base_data: BaseData,
+    /// 上传时间戳
+    pub timestamp: Option<u64>,
+}
+
+/// 所有代码分支
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BranchesData {
+    #[serde(flatten)]
+    pub base_data: BaseData,
+    pub list: Option<Vec<Branch>>,
+}
+
+/// 内存数据，`data` 为解压后的内存内容
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MemoryData<T = Value> {
+    #[serde(flatten)]
+    pub base_data: BaseData,
+    pub data: Option<T>,
+}
+
+/// 内存段数据，`data` 与请求的内存段 id 一一对应，空的内存段为 `None`
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MemorySegmentsData {
+    #[serde(flatten)]
+    pub base_data: BaseData,
+    pub data: Option<Vec<Option<String>>>,
+}
+
+/// 控制台命令数据
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ConsoleData {
+    #[serde(flatten)]
+    pub base_data: BaseData,
+    pub result: Option<ConsoleResult>,
+}
+
+/// 市场订单索引数据
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MarketOrdersIndexData {
+    #[serde(flatten)]
+    pub base_data: BaseData,
+    pub list: Option<Vec<MarketOrderIndex>>,
+}
+
+/// 指定资源的市场订单数据
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MarketOrdersData {
+    #[serde(flatten)]
+    pub base_data: BaseData,
+    pub list: Option<Vec<MarketOrder>>,
+}
+
+/// 我的市场订单数据
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MyMarketOrdersData {
+    #[serde(flatten)]
+    pub base_data: BaseData,
+    /// key 为 shard 名称
+    pub shards: Option<HashMap<String, Vec<MarketOrder>>>,
+}
+
+/// 市场统计数据
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MarketStatsData {
+    #[serde(flatten)]
+    pub base_data: BaseData,
+    pub stats: Option<Vec<MarketStat>>,
+}
+
+/// credits 变动记录数据
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MoneyHistoryData {
+    #[serde(flatten)]
+    pub base_data: BaseData,
+    /// 当前页码，从 0 开始
+    pub page: Option<u32>,
+    pub list: Option<Vec<MoneyHistoryEntry>>,
+    /// 是否还有下一页
+    #[serde(rename = "hasMore")]
+    pub has_more: Option<bool>,
+}
+
+/// 地图统计数据
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MapStatsData {
+    #[serde(flatten)]
+    pub base_data: BaseData,
+    /// key 为房间名
+    pub stats: Option<HashMap<String, MapRoomStat>>,
+    /// key 为用户 id
+    pub users: Option<HashMap<String, UserWithId>>,
+    #[serde(rename = "gameTime")]
+    pub game_time: Option<u64>,
+}
@@ -0,0 +1,232 @@
+//! GCL、GPL 和 RCL 的等级计算
+
+use serde::Serialize;
+
+use crate::types::{Controller, MyInfo, UserInfo};
+
+/// GCL 公式中的倍数
+pub const GCL_MULTIPLY: f64 = 1_000_000.0;
+/// GCL 公式中的幂
+pub const GCL_POW: f64 = 2.4;
+/// GPL 公式中的倍数
+pub const POWER_LEVEL_MULTIPLY: u64 = 1000;
+
+/// 控制器从 1 到 7 级升级到下一级需要的能量
+pub const CONTROLLER_LEVELS: [u64; 7] = [
+    200, 45_000, 135_000, 405_000, 1_215_000, 3_645_000, 10_935_000,
+];
+
+/// 当前等级和升到下一级的进度
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LevelProgress {
+    pub level: u32,
+    /// 当前等级内已经获得的点数
+    pub progress: u64,
+    /// 当前等级升到下一级需要的点数
+    pub progress_total: u64,
+}
+
+impl LevelProgress {
+    /// 升到下一级还需要的点数
+    pub fn remaining(&self) -> u64 {
+        self.progress_total.saturating_sub(self.progress)
+    }
+}
+
+/// 达到指定 GCL 需要的点数
+fn gcl_threshold(level: u32) -> u64 {
+    (GCL_MULTIPLY * (level.saturating_sub(1) as f64).powf(GCL_POW)).ceil() as u64
+}
+
+/// 根据 GCL 点数计算等级，即 `floor((gcl / 1e6) ^ (1 / 2.4)) + 1`
+pub fn gcl_level(gcl: u64) -> u32 {
+    let mut level = (gcl as f64 / GCL_MULTIPLY).powf(1.0 / GCL_POW).floor() as u32 + 1;
+    // 修正浮点误差，保证等级边界和 `gcl_threshold` 一致
+    while gcl_threshold(level + 1) <= gcl {
+        level += 1;
+    }
+    while level > 1 && gcl_threshold(level) > gcl {
+        level -= 1;
+    }
+    level
+}
+
+/// GCL 等级和进度
+pub fn gcl_progress(gcl: u64) -> LevelProgress {
+    let level = gcl_level(gcl);
+    let start = gcl_threshold(level);
+    LevelProgress {
+        level,
+        progress: gcl - start,
+        progress_total: gcl_threshold(level + 1) - start,
+    }
+}
+
+/// 根据 power 总量计算 GPL，即 `floor(sqrt(power / 1000))`
+pub fn gpl_level(power: u64) -> u32 {
+    (power / POWER_LEVEL_MULTIPLY).isqrt() as u32
+}
+
+/// 升到下一级 GPL 还需要的 power
+pub fn power_to_next_level(power: u64) -> u64 {
+    let next = gpl_level(power) as u64 + 1;
+    next * next * POWER_LEVEL_MULTIPLY - power
+}
+
+impl UserInfo {
+    /// GCL 等级
+    pub fn gcl_level(&self) -> u32 {
+        gcl_level(self.gcl)
+    }
+
+    /// GCL 等级和进度
+    pub fn gcl_progress(&self) -> LevelProgress {
+        gcl_progress(self.gcl)
+    }
+
+    /// GPL 等级
+    pub fn gpl_level(&self) -> u32 {
+        gpl_level(self.power)
+    }
+
+    /// 升到下一级 GPL 还需要的 power
+    pub fn power_to_next_level(&self) -> u64 {
+        power_to_next_level(self.power)
+    }
+}
+
+impl MyInfo {
+    /// GCL 等级
+    pub fn gcl_level(&self) -> u32 {
+        gcl_level(self.gcl)
+    }
+
+    /// GCL 等级和进度
+    pub fn gcl_progress(&self) -> LevelProgress {
+        gcl_progress(self.gcl)
+    }
+
+    /// GPL 等级
+    pub fn gpl_level(&self) -> u32 {
+        gpl_level(self.power)
+    }
+
+    /// 升到下一级 GPL 还需要的 power
+    pub fn power_to_next_level(&self) -> u64 {
+        power_to_next_level(self.power)
+    }
+}
+
+impl Controller {
+    /// 升到下一级需要的能量，没有 `progressTotal` 时按控制器等级查表，8 级或未占领时返回 `None`
+    pub fn rcl_progress_total(&self) -> Option<u64> {
+        if let Some(total) = self
+            .progress_total
+            .and_then(|total| u64::try_from(total).ok())
+            && total > 0
+        {
+            return Some(total);
+        }
+        let index = usize::try_from(self.level).ok()?.checked_sub(1)?;
+        CONTROLLER_LEVELS.get(index).copied()
+    }
+
+    /// RCL 等级和进度，8 级或未占领时返回 `None`
+    pub fn rcl_progress(&self) -> Option<LevelProgress> {
+        let progress_total = self.rcl_progress_total()?;
+        Some(LevelProgress {
+            level: self.level as u32,
+            progress: self.progress.unwrap_or(0).max(0) as u64,
+            progress_total,
+        })
+    }
+
+    /// 距离降级的 tick 数，没有 `downgradeTime` 时返回 `None`
+    ///
+    /// 参数：
+    /// - game_time: 当前游戏时间，可以通过 `get_shard_time` 获取
+    pub fn ticks_to_downgrade(&self, game_time: u64) -> Option<u64> {
+        self.downgrade_time
+            .map(|downgrade_time| downgrade_time.saturating_sub(game_time))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::RoomObject;
+
+    #[test]
+    fn test_gcl() {
+        assert_eq!(gcl_level(0), 1);
+        assert_eq!(gcl_level(999_999), 1);
+        assert_eq!(gcl_level(1_000_000), 2);
+        assert_eq!(gcl_level(5_278_031), 2);
+        assert_eq!(gcl_level(5_278_032), 3);
+        assert_eq!(
+            gcl_progress(3_000_000),
+            LevelProgress {
+                level: 2,
+                progress: 2_000_000,
+                progress_total: 4_278_032,
+            }
+        );
+        assert_eq!(gcl_progress(3_000_000).remaining(), 2_278_032);
+    }
+
+    #[test]
+    fn test_gpl() {
+        assert_eq!(gpl_level(0), 0);
+        assert_eq!(gpl_level(999), 0);
+        assert_eq!(gpl_level(1000), 1);
+        assert_eq!(gpl_level(3999), 1);
+        assert_eq!(gpl_level(4000), 2);
+        assert_eq!(power_to_next_level(0), 1000);
+        assert_eq!(power_to_next_level(1500), 2500);
+        assert_eq!(power_to_next_level(4000), 5000);
+
+        let user: UserInfo = serde_json::from_str(
+            r#"{ "_id": "u1", "username": "keqing", "gcl": 1000000, "power": 4000 }"#,
+        )
+        .unwrap();
+        assert_eq!(user.gcl_level(), 2);
+        assert_eq!(user.gcl_progress().progress, 0);
+        assert_eq!(user.gpl_level(), 2);
+        assert_eq!(user.power_to_next_level(), 5000);
+    }
+
+    #[test]
+    fn test_controller_progress() {
+        let controller = |json: &str| match serde_json::from_str(json).unwrap() {
+            RoomObject::Controller(controller) => controller,
+            other => panic!("unexpected object {:?}", other),
+        };
+        let owned = controller(
+            r#"{ "_id": "c", "type": "controller", "room": "E13S13", "x": 1, "y": 1,
+                 "level": 4, "progress": 100000, "user": "u1", "downgradeTime": 20500 }"#,
+        );
+        assert_eq!(owned.rcl_progress_total(), Some(405_000));
+        assert_eq!(
+            owned.rcl_progress(),
+            Some(LevelProgress {
+                level: 4,
+                progress: 100_000,
+                progress_total: 405_000,
+            })
+        );
+        assert_eq!(owned.ticks_to_downgrade(20_000), Some(500));
+        assert_eq!(owned.ticks_to_downgrade(30_000), Some(0));
+
+        let max = controller(
+            r#"{ "_id": "c", "type": "controller", "room": "E13S13", "x": 1, "y": 1,
+                 "level": 8, "progress": 0, "user": "u1" }"#,
+        );
+        assert_eq!(max.rcl_progress(), None);
+        assert_eq!(max.ticks_to_downgrade(20_000), None);
+
+        let neutral = controller(
+            r#"{ "_id": "c", "type": "controller", "room": "E13S13", "x": 1, "y": 1, "level": 0 }"#,
+        );
+        assert_eq!(neutral.rcl_progress_total(), None);
+    }
+}
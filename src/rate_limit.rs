@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, time::Duration};
 
 use reqwest::header::HeaderMap;
 
@@ -80,6 +80,53 @@ impl RateLimits {
         }
     }
 
+    /// 获取指定请求对应的限速 key，没有单独限速的接口共用全局限速
+    pub fn limit_key(&self, method: &Method, path: &str) -> String {
+        let (name, limits) = match method {
+            Method::Get => ("GET", &self.get_limits),
+            Method::Post => ("POST", &self.post_limits),
+        };
+        if limits.contains_key(path) {
+            format!("{} {}", name, path)
+        } else {
+            "global".to_string()
+        }
+    }
+
+    /// 尝试占用一次请求次数，成功时先扣减剩余次数再返回 `None`，次数用完时返回需要等待的时间
+    /// 参数：
+    /// - method: 请求方法，GET or POST
+    /// - path: 请求路径
+    /// - now: 当前时间戳，单位 ms
+    pub fn try_acquire(&mut self, method: &Method, path: &str, now: u128) -> Option<Duration> {
+        let rate_limit = self.get_limit_mut(method, path);
+        if rate_limit.remaining > 0 {
+            rate_limit.remaining -= 1;
+            return None;
+        }
+        let reset = rate_limit.reset * 1000;
+        if now >= reset {
+            // 已过重置时间，新的周期内次数恢复
+            rate_limit.remaining = rate_limit.limit - 1;
+            return None;
+        }
+        Some(Duration::from_millis((reset - now) as u64))
+    }
+
+    /// 当前周期内还能发送的请求次数，已过重置时间时返回完整的限制次数
+    /// 参数：
+    /// - method: 请求方法，GET or POST
+    /// - path: 请求路径
+    /// - now: 当前时间戳，单位 ms
+    pub fn available(&self, method: &Method, path: &str, now: u128) -> i32 {
+        let rate_limit = self.get_limit(method, path);
+        if rate_limit.remaining <= 0 && now >= rate_limit.reset * 1000 {
+            rate_limit.limit
+        } else {
+            rate_limit.remaining
+        }
+    }
+
     /// 更新限速信息
     /// 参数：
     /// - method: 请求方法，GET or POST
@@ -108,8 +155,13 @@ impl RateLimits {
             .and_then(|s| s.to_str().unwrap_or("0").parse::<u128>().ok())
             .unwrap_or(0);
         let rate_limit = self.get_limit_mut(method, path);
+        // 同一周期内本地已经为并发中的请求预先扣减了次数，取较小值避免超出限制
+        if rate_limit.reset == reset {
+            rate_limit.remaining = rate_limit.remaining.min(remaining);
+        } else {
+            rate_limit.remaining = remaining;
+        }
         rate_limit.limit = limit;
-        rate_limit.remaining = remaining;
         rate_limit.reset = reset;
     }
 }
@@ -154,7 +206,7 @@ impl Default for RateLimits {
             RateLimit::default(60, Period::Hour),
         );
         get_limits.insert(
-            "/game/user/money-history".to_string(),
+            "/user/money-history".to_string(),
             RateLimit::default(60, Period::Hour),
         );
 
@@ -191,3 +243,78 @@ impl Default for RateLimits {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limit_key() {
+        let rate_limits = RateLimits::default();
+        assert_eq!(
+            rate_limits.limit_key(&Method::Get, "/game/room-terrain"),
+            "GET /game/room-terrain"
+        );
+        assert_eq!(
+            rate_limits.limit_key(&Method::Post, "/game/room-terrain"),
+            "global"
+        );
+        assert_eq!(rate_limits.limit_key(&Method::Get, "/auth/me"), "global");
+    }
+
+    #[test]
+    fn test_try_acquire() {
+        let mut rate_limits = RateLimits::default();
+        let path = "/game/room-terrain";
+        rate_limits.update_limit(
+            &Method::Get,
+            path,
+            RateLimit::new(2, Period::Hour, 2, 100),
+        );
+
+        assert_eq!(rate_limits.try_acquire(&Method::Get, path, 1_000), None);
+        assert_eq!(rate_limits.try_acquire(&Method::Get, path, 1_000), None);
+        assert_eq!(
+            rate_limits.try_acquire(&Method::Get, path, 1_000),
+            Some(Duration::from_millis(99_000))
+        );
+        // 过了重置时间后恢复
+        assert_eq!(rate_limits.try_acquire(&Method::Get, path, 100_000), None);
+        assert_eq!(rate_limits.get_limit(&Method::Get, path).remaining, 1);
+    }
+
+    #[test]
+    fn test_available() {
+        let mut rate_limits = RateLimits::default();
+        let path = "/game/room-terrain";
+        assert_eq!(rate_limits.available(&Method::Get, path, 0), 360);
+        rate_limits.update_limit(
+            &Method::Get,
+            path,
+            RateLimit::new(360, Period::Hour, 0, 100),
+        );
+        assert_eq!(rate_limits.available(&Method::Get, path, 1_000), 0);
+        assert_eq!(rate_limits.available(&Method::Get, path, 100_000), 360);
+    }
+
+    #[test]
+    fn test_update_from_headers_keeps_local_reservations() {
+        let mut rate_limits = RateLimits::default();
+        let path = "/game/room-terrain";
+        rate_limits.update_limit(
+            &Method::Get,
+            path,
+            RateLimit::new(360, Period::Hour, 10, 100),
+        );
+        let mut headers = HeaderMap::new();
+        headers.insert("x-ratelimit-limit", "360".parse().unwrap());
+        headers.insert("x-ratelimit-remaining", "20".parse().unwrap());
+        headers.insert("x-ratelimit-reset", "100".parse().unwrap());
+        rate_limits.update_from_headers(&Method::Get, path, &headers);
+        assert_eq!(rate_limits.get_limit(&Method::Get, path).remaining, 10);
+
+        headers.insert("x-ratelimit-reset", "200".parse().unwrap());
+        rate_limits.update_from_headers(&Method::Get, path, &headers);
+        assert_eq!(rate_limits.get_limit(&Method::Get, path).remaining, 20);
+    }
+}
@@ -0,0 +1,368 @@
+//! 资源类型和对象存储
+
+use std::{
+    collections::HashMap,
+    fmt,
+    ops::{Add, AddAssign},
+    str::FromStr,
+};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// 定义资源类型枚举以及名称和枚举之间的转换
+macro_rules! resource_types {
+    ($($(#[$meta:meta])* $variant:ident => $name:literal,)*) => {
+        /// 资源类型，未知的资源保存为 `ResourceType::Unknown`
+        #[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+        pub enum ResourceType {
+            $($(#[$meta])* $variant,)*
+            /// 未知的资源，例如赛季服或私服新增的资源
+            Unknown(String),
+        }
+
+        impl ResourceType {
+            /// 所有已知的资源类型
+            pub const ALL: &'static [ResourceType] = &[$(ResourceType::$variant,)*];
+
+            /// 游戏中的资源名称，例如 `energy`、`XGH2O`
+            pub fn as_str(&self) -> &str {
+                match self {
+                    $(Self::$variant => $name,)*
+                    Self::Unknown(name) => name,
+                }
+            }
+        }
+
+        impl From<&str> for ResourceType {
+            fn from(name: &str) -> Self {
+                match name {
+                    $($name => Self::$variant,)*
+                    _ => Self::Unknown(name.to_string()),
+                }
+            }
+        }
+    };
+}
+
+resource_types! {
+    Energy => "energy",
+    Power => "power",
+    Ops => "ops",
+
+    // 基础矿物
+    Hydrogen => "H",
+    Oxygen => "O",
+    Utrium => "U",
+    Lemergium => "L",
+    Keanium => "K",
+    Zynthium => "Z",
+    Catalyst => "X",
+    Ghodium => "G",
+
+    // 基础化合物
+    Hydroxide => "OH",
+    ZynthiumKeanite => "ZK",
+    UtriumLemergite => "UL",
+
+    // T1 化合物
+    UtriumHydride => "UH",
+    UtriumOxide => "UO",
+    KeaniumHydride => "KH",
+    KeaniumOxide => "KO",
+    LemergiumHydride => "LH",
+    LemergiumOxide => "LO",
+    ZynthiumHydride => "ZH",
+    ZynthiumOxide => "ZO",
+    GhodiumHydride => "GH",
+    GhodiumOxide => "GO",
+
+    // T2 化合物
+    UtriumAcid => "UH2O",
+    UtriumAlkalide => "UHO2",
+    KeaniumAcid => "KH2O",
+    KeaniumAlkalide => "KHO2",
+    LemergiumAcid => "LH2O",
+    LemergiumAlkalide => "LHO2",
+    ZynthiumAcid => "ZH2O",
+    ZynthiumAlkalide => "ZHO2",
+    GhodiumAcid => "GH2O",
+    GhodiumAlkalide => "GHO2",
+
+    // T3 化合物
+    CatalyzedUtriumAcid => "XUH2O",
+    CatalyzedUtriumAlkalide => "XUHO2",
+    CatalyzedKeaniumAcid => "XKH2O",
+    CatalyzedKeaniumAlkalide => "XKHO2",
+    CatalyzedLemergiumAcid => "XLH2O",
+    CatalyzedLemergiumAlkalide => "XLHO2",
+    CatalyzedZynthiumAcid => "XZH2O",
+    CatalyzedZynthiumAlkalide => "XZHO2",
+    CatalyzedGhodiumAcid => "XGH2O",
+    CatalyzedGhodiumAlkalide => "XGHO2",
+
+    // 沉积物
+    Silicon => "silicon",
+    Metal => "metal",
+    Biomass => "biomass",
+    Mist => "mist",
+
+    // 压缩商品
+    UtriumBar => "utrium_bar",
+    LemergiumBar => "lemergium_bar",
+    ZynthiumBar => "zynthium_bar",
+    KeaniumBar => "keanium_bar",
+    GhodiumMelt => "ghodium_melt",
+    Oxidant => "oxidant",
+    Reductant => "reductant",
+    Purifier => "purifier",
+    Battery => "battery",
+
+    // 通用商品
+    Composite => "composite",
+    Crystal => "crystal",
+    Liquid => "liquid",
+
+    // 电子商品
+    Wire => "wire",
+    Switch => "switch",
+    Transistor => "transistor",
+    Microchip => "microchip",
+    Circuit => "circuit",
+    Device => "device",
+
+    // 生物商品
+    Cell => "cell",
+    Phlegm => "phlegm",
+    Tissue => "tissue",
+    Muscle => "muscle",
+    Organoid => "organoid",
+    Organism => "organism",
+
+    // 机械商品
+    Alloy => "alloy",
+    Tube => "tube",
+    Fixtures => "fixtures",
+    Frame => "frame",
+    Hydraulics => "hydraulics",
+    Machine => "machine",
+
+    // 神秘商品
+    Condensate => "condensate",
+    Concentrate => "concentrate",
+    Extract => "extract",
+    Spirit => "spirit",
+    Emanation => "emanation",
+    Essence => "essence",
+}
+
+impl ResourceType {
+    /// 是否为基础矿物，包括 ghodium
+    pub fn is_mineral(&self) -> bool {
+        matches!(
+            self,
+            Self::Hydrogen
+                | Self::Oxygen
+                | Self::Utrium
+                | Self::Lemergium
+                | Self::Keanium
+                | Self::Zynthium
+                | Self::Catalyst
+                | Self::Ghodium
+        )
+    }
+}
+
+impl fmt::Display for ResourceType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ResourceType {
+    type Err = std::convert::Infallible;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Ok(name.into())
+    }
+}
+
+impl Serialize for ResourceType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for ResourceType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        Ok(name.as_str().into())
+    }
+}
+
+/// 对象中存储的资源，例如 storage、terminal、creep 的 `store`
+///
+/// 服务器返回的 `null` 会被忽略，数量使用 `i64`，便于汇总多个对象的资源。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Store(HashMap<ResourceType, i64>);
+
+impl Store {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 指定资源的数量，没有该资源时返回 0
+    pub fn get(&self, resource: ResourceType) -> i64 {
+        self.0.get(&resource).copied().unwrap_or(0)
+    }
+
+    /// 设置指定资源的数量
+    pub fn set(&mut self, resource: ResourceType, amount: i64) {
+        self.0.insert(resource, amount);
+    }
+
+    /// 增加指定资源的数量
+    pub fn add_resource(&mut self, resource: ResourceType, amount: i64) {
+        *self.0.entry(resource).or_insert(0) += amount;
+    }
+
+    /// 合并另一个存储中的资源
+    pub fn merge(&mut self, other: &Store) {
+        for (resource, amount) in other.iter() {
+            self.add_resource(resource.clone(), amount);
+        }
+    }
+
+    /// 所有资源的总量
+    pub fn total(&self) -> i64 {
+        self.0.values().sum()
+    }
+
+    /// 遍历所有资源和数量
+    pub fn iter(&self) -> impl Iterator<Item = (&ResourceType, i64)> {
+        self.0.iter().map(|(resource, amount)| (resource, *amount))
+    }
+
+    /// 资源种类数量
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// 是否没有任何资源
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl Add for Store {
+    type Output = Store;
+
+    fn add(mut self, other: Store) -> Store {
+        self += other;
+        self
+    }
+}
+
+impl AddAssign for Store {
+    fn add_assign(&mut self, other: Store) {
+        for (resource, amount) in other.0 {
+            self.add_resource(resource, amount);
+        }
+    }
+}
+
+impl AddAssign<&Store> for Store {
+    fn add_assign(&mut self, other: &Store) {
+        self.merge(other);
+    }
+}
+
+impl FromIterator<(ResourceType, i64)> for Store {
+    fn from_iter<I: IntoIterator<Item = (ResourceType, i64)>>(iter: I) -> Self {
+        let mut store = Store::new();
+        for (resource, amount) in iter {
+            store.add_resource(resource, amount);
+        }
+        store
+    }
+}
+
+impl IntoIterator for Store {
+    type Item = (ResourceType, i64);
+    type IntoIter = std::collections::hash_map::IntoIter<ResourceType, i64>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl Serialize for Store {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.0.iter().map(|(k, v)| (k.as_str(), v)))
+    }
+}
+
+impl<'de> Deserialize<'de> for Store {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = HashMap::<ResourceType, Option<i64>>::deserialize(deserializer)?;
+        Ok(raw
+            .into_iter()
+            .filter_map(|(resource, amount)| Some((resource, amount?)))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resource_type() {
+        assert_eq!(ResourceType::from("energy"), ResourceType::Energy);
+        assert_eq!(
+            ResourceType::from("XGH2O"),
+            ResourceType::CatalyzedGhodiumAcid
+        );
+        assert_eq!(
+            ResourceType::from("season_token"),
+            ResourceType::Unknown("season_token".to_string())
+        );
+        for resource in ResourceType::ALL {
+            assert_eq!(&ResourceType::from(resource.as_str()), resource);
+        }
+        assert!(ResourceType::Ghodium.is_mineral());
+        assert!(!ResourceType::GhodiumHydride.is_mineral());
+    }
+
+    #[test]
+    fn test_store_serde() {
+        let store: Store =
+            serde_json::from_str(r#"{"energy": 1000, "XUH2O": 30, "battery": null, "thing": 5}"#)
+                .unwrap();
+        assert_eq!(store.len(), 3);
+        assert_eq!(store.get(ResourceType::Energy), 1000);
+        assert_eq!(store.get(ResourceType::CatalyzedUtriumAcid), 30);
+        assert_eq!(store.get(ResourceType::Battery), 0);
+        assert_eq!(store.get(ResourceType::Unknown("thing".to_string())), 5);
+        assert_eq!(store.total(), 1035);
+
+        let json = serde_json::to_value(&store).unwrap();
+        assert_eq!(json["XUH2O"], 30);
+        assert_eq!(serde_json::from_value::<Store>(json).unwrap(), store);
+    }
+
+    #[test]
+    fn test_store_merge() {
+        let a: Store = [(ResourceType::Energy, 100), (ResourceType::Hydrogen, 5)]
+            .into_iter()
+            .collect();
+        let b: Store = [(ResourceType::Energy, 50), (ResourceType::Power, 7)]
+            .into_iter()
+            .collect();
+        let mut merged = a.clone();
+        merged.merge(&b);
+        assert_eq!(merged, a + b);
+        assert_eq!(merged.get(ResourceType::Energy), 150);
+        assert_eq!(merged.get(ResourceType::Power), 7);
+        assert_eq!(merged.total(), 162);
+    }
+}
@@ -0,0 +1,295 @@
+//! 房间名称
+//!
+//! 世界坐标以 `E0S0` 为原点，向东为 x 正方向，向南为 y 正方向：
+//! `E{n}` 对应 x = n，`W{n}` 对应 x = -n - 1，`S{n}` 对应 y = n，`N{n}` 对应 y = -n - 1。
+
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{ScreepsError, ScreepsResult};
+
+/// 房间名中数字的最大值，官方服务器的地图远小于这个范围
+pub const MAX_ROOM_NUMBER: i32 = 255;
+
+/// 房间名称，例如 `E13S13`、`W0N0`
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(try_from = "String", into = "String")]
+pub struct RoomName {
+    /// 世界坐标 x
+    x: i32,
+    /// 世界坐标 y
+    y: i32,
+}
+
+impl RoomName {
+    /// 解析房间名称，不区分大小写，数字超过 `MAX_ROOM_NUMBER` 时返回 `InvalidParam`
+    pub fn new(name: &str) -> ScreepsResult<Self> {
+        name.parse()
+    }
+
+    /// 根据世界坐标构造房间名称，坐标不做范围检查
+    pub fn from_coords(x: i32, y: i32) -> Self {
+        Self { x, y }
+    }
+
+    /// 世界坐标 (x, y)
+    pub fn coords(&self) -> (i32, i32) {
+        (self.x, self.y)
+    }
+
+    /// 房间名中东西方向的数字
+    fn x_number(&self) -> i32 {
+        if self.x >= 0 { self.x } else { -(self.x + 1) }
+    }
+
+    /// 房间名中南北方向的数字
+    fn y_number(&self) -> i32 {
+        if self.y >= 0 { self.y } else { -(self.y + 1) }
+    }
+
+    /// 到另一个房间的直线距离，即 `Game.map.getRoomLinearDistance`
+    pub fn linear_distance(&self, other: &RoomName) -> u32 {
+        self.x.abs_diff(other.x).max(self.y.abs_diff(other.y))
+    }
+
+    /// 到另一个房间的曼哈顿距离
+    pub fn manhattan_distance(&self, other: &RoomName) -> u32 {
+        self.x
+            .abs_diff(other.x)
+            .saturating_add(self.y.abs_diff(other.y))
+    }
+
+    /// 是否为过道房间
+    pub fn is_highway(&self) -> bool {
+        self.x_number() % 10 == 0 || self.y_number() % 10 == 0
+    }
+
+    /// 是否为十字路口房间，即两个方向都是过道
+    pub fn is_crossroad(&self) -> bool {
+        self.x_number() % 10 == 0 && self.y_number() % 10 == 0
+    }
+
+    /// 是否为扇区中心房间
+    pub fn is_center(&self) -> bool {
+        self.x_number() % 10 == 5 && self.y_number() % 10 == 5
+    }
+
+    /// 是否为 source keeper 房间，即中心房间周围的 8 个房间
+    pub fn is_source_keeper(&self) -> bool {
+        let x = self.x_number() % 10;
+        let y = self.y_number() % 10;
+        (4..=6).contains(&x) && (4..=6).contains(&y) && !self.is_center()
+    }
+
+    /// 通过出口相连的 4 个房间，顺序为上、右、下、左，坐标到达 i32 边界时不再移动
+    pub fn neighbors(&self) -> [RoomName; 4] {
+        [
+            Self::from_coords(self.x, self.y.saturating_sub(1)),
+            Self::from_coords(self.x.saturating_add(1), self.y),
+            Self::from_coords(self.x, self.y.saturating_add(1)),
+            Self::from_coords(self.x.saturating_sub(1), self.y),
+        ]
+    }
+
+    /// 所在扇区的中心房间，过道房间归属于数字较大一侧的扇区，例如 `E20S9` 属于 `E25S5`
+    pub fn sector(&self) -> RoomName {
+        let center = |coord: i32, number: i32| {
+            let number = number / 10 * 10 + 5;
+            if coord >= 0 { number } else { -number - 1 }
+        };
+        Self::from_coords(
+            center(self.x, self.x_number()),
+            center(self.y, self.y_number()),
+        )
+    }
+}
+
+impl fmt::Display for RoomName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let horizontal = if self.x >= 0 { 'E' } else { 'W' };
+        let vertical = if self.y >= 0 { 'S' } else { 'N' };
+        write!(
+            f,
+            "{}{}{}{}",
+            horizontal,
+            self.x_number(),
+            vertical,
+            self.y_number()
+        )
+    }
+}
+
+impl FromStr for RoomName {
+    type Err = ScreepsError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        let invalid = || ScreepsError::InvalidParam(format!("invalid room name: {}", name));
+        let upper = name.to_ascii_uppercase();
+        let vertical_index = upper.find(['N', 'S']).ok_or_else(invalid)?;
+        let (horizontal, vertical) = upper.split_at(vertical_index);
+        let parse = |part: &str, positive: char, negative: char| {
+            let mut chars = part.chars();
+            let direction = chars.next()?;
+            let digits = chars.as_str();
+            if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+                return None;
+            }
+            let number: i32 = digits.parse().ok()?;
+            if number > MAX_ROOM_NUMBER {
+                return None;
+            }
+            match direction {
+                d if d == positive => Some(number),
+                d if d == negative => Some(-number - 1),
+                _ => None,
+            }
+        };
+        let x = parse(horizontal, 'E', 'W').ok_or_else(invalid)?;
+        let y = parse(vertical, 'S', 'N').ok_or_else(invalid)?;
+        Ok(Self { x, y })
+    }
+}
+
+impl TryFrom<String> for RoomName {
+    type Error = ScreepsError;
+
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        name.parse()
+    }
+}
+
+impl From<RoomName> for String {
+    fn from(room: RoomName) -> Self {
+        room.to_string()
+    }
+}
+
+/// 可以转换为房间名称的类型，接口在发送请求前会先校验房间名称
+pub trait IntoRoomName {
+    fn into_room_name(self) -> ScreepsResult<RoomName>;
+}
+
+impl IntoRoomName for RoomName {
+    fn into_room_name(self) -> ScreepsResult<RoomName> {
+        Ok(self)
+    }
+}
+
+impl IntoRoomName for &RoomName {
+    fn into_room_name(self) -> ScreepsResult<RoomName> {
+        Ok(*self)
+    }
+}
+
+impl IntoRoomName for &str {
+    fn into_room_name(self) -> ScreepsResult<RoomName> {
+        self.parse()
+    }
+}
+
+impl IntoRoomName for String {
+    fn into_room_name(self) -> ScreepsResult<RoomName> {
+        self.parse()
+    }
+}
+
+impl IntoRoomName for &String {
+    fn into_room_name(self) -> ScreepsResult<RoomName> {
+        self.parse()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn room(name: &str) -> RoomName {
+        name.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse_and_display() {
+        assert_eq!(room("E13S13").coords(), (13, 13));
+        assert_eq!(room("W0N0").coords(), (-1, -1));
+        assert_eq!(room("E0S0").coords(), (0, 0));
+        assert_eq!(room("w5n12").to_string(), "W5N12");
+        assert_eq!(RoomName::from_coords(-6, 3).to_string(), "W5S3");
+
+        for name in [
+            "",
+            "E13",
+            "S13",
+            "E13S",
+            "ES13",
+            "X1S1",
+            "E1X1",
+            "E-1S1",
+            "E1S1S",
+            "sim",
+            "E256S0",
+            "W2147483647N0",
+            "E0S99999999999",
+        ] {
+            assert!(
+                matches!(RoomName::new(name), Err(ScreepsError::InvalidParam(_))),
+                "{}",
+                name
+            );
+        }
+
+        assert_eq!(room("W255N255").coords(), (-256, -256));
+        assert_eq!(room("E255S255").to_string(), "E255S255");
+
+        // from_coords 不检查范围，极端坐标也不能溢出
+        let min = RoomName::from_coords(i32::MIN, i32::MIN);
+        assert_eq!(min.to_string(), "W2147483647N2147483647");
+        assert_eq!(min.neighbors()[0], min);
+        assert_eq!(min.sector().to_string(), "W2147483645N2147483645");
+        let max = RoomName::from_coords(i32::MAX, i32::MAX);
+        assert_eq!(max.neighbors()[1], max);
+        assert_eq!(min.manhattan_distance(&max), u32::MAX);
+    }
+
+    #[test]
+    fn test_serde() {
+        let name: RoomName = serde_json::from_str(r#""E13S13""#).unwrap();
+        assert_eq!(name, room("E13S13"));
+        assert_eq!(serde_json::to_string(&name).unwrap(), r#""E13S13""#);
+        assert!(serde_json::from_str::<RoomName>(r#""nowhere""#).is_err());
+    }
+
+    #[test]
+    fn test_distance() {
+        // 跨过原点时 W0 和 E0 相邻
+        assert_eq!(room("W0N0").linear_distance(&room("E0S0")), 1);
+        assert_eq!(room("W0N0").manhattan_distance(&room("E0S0")), 2);
+        assert_eq!(room("E13S13").linear_distance(&room("E10S20")), 7);
+        assert_eq!(room("E13S13").manhattan_distance(&room("E10S20")), 10);
+    }
+
+    #[test]
+    fn test_room_kinds() {
+        assert!(room("E10S13").is_highway());
+        assert!(room("W13N0").is_highway());
+        assert!(!room("E13S13").is_highway());
+        assert!(room("E10S20").is_crossroad());
+        assert!(!room("E10S13").is_crossroad());
+        assert!(room("E15S15").is_center());
+        assert!(room("W5N5").is_center());
+        assert!(room("E14S16").is_source_keeper());
+        assert!(!room("E15S15").is_source_keeper());
+        assert!(!room("E13S15").is_source_keeper());
+    }
+
+    #[test]
+    fn test_neighbors_and_sector() {
+        assert_eq!(
+            room("E0N0").neighbors().map(|r| r.to_string()),
+            ["E0N1", "E1N0", "E0S0", "W0N0"]
+        );
+        assert_eq!(room("E13S13").sector(), room("E15S15"));
+        assert_eq!(room("W3N19").sector(), room("W5N15"));
+        assert_eq!(room("E20S9").sector(), room("E25S5"));
+    }
+}
@@ -0,0 +1,260 @@
+//! 根据 websocket 房间频道的差异数据维护房间的完整状态
+
+use std::collections::HashMap;
+
+use serde_json::{Map, Value};
+
+use crate::{
+    socket::{RoomEvent, SocketEvent},
+    types::RoomObject,
+};
+
+/// 房间的当前状态
+///
+/// 房间频道第一次推送的是全部对象，之后只推送变化的字段，值为 `null` 表示对象或字段被删除。
+/// `RoomState` 保存合并后的原始 json，并将变化的对象重新反序列化为 `RoomObject`。
+#[derive(Debug)]
+pub struct RoomState {
+    /// shard 名称
+    pub shard: String,
+    /// 房间名称
+    pub room: String,
+    /// 最近一次推送的游戏时间
+    tick: Option<u64>,
+    /// 合并后的原始对象数据，key 为对象 `_id`
+    raw: HashMap<String, Value>,
+    /// 反序列化后的对象，缺少 `type` 字段的对象只保留原始数据
+    objects: HashMap<String, RoomObject>,
+}
+
+impl RoomState {
+    pub fn new(shard: &str, room: &str) -> Self {
+        Self {
+            shard: shard.to_string(),
+            room: room.to_string(),
+            tick: None,
+            raw: HashMap::new(),
+            objects: HashMap::new(),
+        }
+    }
+
+    /// 应用一次房间推送
+    pub fn apply(&mut self, event: &RoomEvent) {
+        if let Some(game_time) = event.game_time {
+            self.tick = Some(game_time);
+        }
+        for (id, diff) in &event.objects {
+            if diff.is_null() {
+                self.raw.remove(id);
+                self.objects.remove(id);
+                continue;
+            }
+            let object = self
+                .raw
+                .entry(id.clone())
+                .or_insert_with(|| Value::Object(Map::new()));
+            merge(object, diff);
+            match serde_json::from_value(object.clone()) {
+                Ok(room_object) => {
+                    self.objects.insert(id.clone(), room_object);
+                }
+                Err(_) => {
+                    self.objects.remove(id);
+                }
+            }
+        }
+    }
+
+    /// 清空所有对象，等待下一次完整推送
+    pub fn clear(&mut self) {
+        self.tick = None;
+        self.raw.clear();
+        self.objects.clear();
+    }
+
+    /// 应用 websocket 事件，返回状态是否有变化
+    ///
+    /// 只处理本房间的房间事件。连接断开或重连成功时会清空状态，因为断线期间删除的对象不会再收到
+    /// `null` 差异，重连后服务器推送的完整数据会重新建立状态。收到 `Lagged` 时可能丢失了差异，
+    /// 同样清空状态，等待重新订阅后的完整推送。
+    pub fn apply_socket_event(&mut self, event: &SocketEvent) -> bool {
+        match event {
+            SocketEvent::Room { shard, room, event }
+                if *shard == self.shard && *room == self.room =>
+            {
+                self.apply(event);
+                true
+            }
+            SocketEvent::Connected
+            | SocketEvent::Disconnected { .. }
+            | SocketEvent::Lagged { .. } => {
+                self.clear();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// 最近一次推送的游戏时间
+    pub fn tick(&self) -> Option<u64> {
+        self.tick
+    }
+
+    /// 根据 `_id` 获取对象
+    pub fn get(&self, id: &str) -> Option<&RoomObject> {
+        self.objects.get(id)
+    }
+
+    /// 根据 `_id` 获取对象的原始数据
+    pub fn raw(&self, id: &str) -> Option<&Value> {
+        self.raw.get(id)
+    }
+
+    /// 所有对象
+    pub fn objects(&self) -> impl Iterator<Item = &RoomObject> {
+        self.objects.values()
+    }
+
+    /// 对象数量，包括反序列化失败的对象
+    pub fn len(&self) -> usize {
+        self.raw.len()
+    }
+
+    /// 房间内是否没有对象
+    pub fn is_empty(&self) -> bool {
+        self.raw.is_empty()
+    }
+}
+
+/// 将差异合并到原始数据中，对象递归合并，`null` 表示删除字段，其他值直接覆盖
+fn merge(target: &mut Value, diff: &Value) {
+    match (target, diff) {
+        (Value::Object(target), Value::Object(diff)) => {
+            for (key, value) in diff {
+                if value.is_null() {
+                    target.remove(key);
+                } else {
+                    merge(target.entry(key.clone()).or_insert(Value::Null), value);
+                }
+            }
+        }
+        (target, diff) => *target = diff.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn event(data: Value) -> RoomEvent {
+        serde_json::from_value(data).unwrap()
+    }
+
+    #[test]
+    fn test_apply_diff() {
+        let mut state = RoomState::new("shard3", "E13S13");
+        state.apply(&event(json!({
+            "gameTime": 100,
+            "objects": {
+                "s1": {
+                    "_id": "s1", "type": "source", "room": "E13S13", "x": 10, "y": 20,
+                    "energy": 3000, "energyCapacity": 3000, "ticksToRegeneration": 300,
+                    "invaderHarvested": 0
+                },
+                "r1": {
+                    "_id": "r1", "type": "road", "room": "E13S13", "x": 11, "y": 20,
+                    "hits": 5000, "hitsMax": 5000, "notifyWhenAttacked": true,
+                    "nextDecayTime": 1000
+                }
+            }
+        })));
+        assert_eq!(state.tick(), Some(100));
+        assert_eq!(state.len(), 2);
+
+        state.apply(&event(json!({
+            "gameTime": 101,
+            "objects": {
+                "s1": { "energy": 2990 },
+                "r1": null
+            }
+        })));
+        assert_eq!(state.tick(), Some(101));
+        assert_eq!(state.len(), 1);
+        assert!(state.get("r1").is_none());
+        match state.get("s1") {
+            Some(RoomObject::Source(source)) => {
+                assert_eq!(source.energy, 2990);
+                assert_eq!(source.base_object.x, 10);
+            }
+            object => panic!("unexpected object {:?}", object),
+        }
+    }
+
+    #[test]
+    fn test_merge_removes_null_fields() {
+        let mut target = json!({ "a": 1, "b": { "c": 2, "d": 3 } });
+        merge(
+            &mut target,
+            &json!({ "a": null, "b": { "c": null, "e": 4 } }),
+        );
+        assert_eq!(target, json!({ "b": { "d": 3, "e": 4 } }));
+    }
+
+    #[test]
+    fn test_apply_socket_event_ignores_other_rooms() {
+        let mut state = RoomState::new("shard3", "E13S13");
+        let other = SocketEvent::Room {
+            shard: "shard3".to_string(),
+            room: "E14S13".to_string(),
+            event: event(json!({ "gameTime": 5, "objects": {} })),
+        };
+        assert!(!state.apply_socket_event(&other));
+        assert_eq!(state.tick(), None);
+    }
+
+    #[test]
+    fn test_reconnect_drops_objects_deleted_while_disconnected() {
+        let source = json!({
+            "_id": "s1", "type": "source", "room": "E13S13", "x": 10, "y": 20,
+            "energy": 3000, "energyCapacity": 3000, "ticksToRegeneration": 300,
+            "invaderHarvested": 0
+        });
+        let road = json!({
+            "_id": "r1", "type": "road", "room": "E13S13", "x": 11, "y": 20,
+            "hits": 5000, "hitsMax": 5000, "notifyWhenAttacked": true, "nextDecayTime": 1000
+        });
+        let room_event = |data: Value| SocketEvent::Room {
+            shard: "shard3".to_string(),
+            room: "E13S13".to_string(),
+            event: event(data),
+        };
+
+        let mut state = RoomState::new("shard3", "E13S13");
+        state.apply_socket_event(&room_event(json!({
+            "gameTime": 100,
+            "objects": { "s1": source.clone(), "r1": road }
+        })));
+        assert_eq!(state.len(), 2);
+
+        // 断线期间 r1 被删除，重连后的完整推送中不再包含 r1
+        assert!(state.apply_socket_event(&SocketEvent::Disconnected {
+            reason: "connection closed".to_string(),
+        }));
+        assert!(state.apply_socket_event(&SocketEvent::Connected));
+        assert!(state.is_empty());
+        state.apply_socket_event(&room_event(json!({
+            "gameTime": 200,
+            "objects": { "s1": source }
+        })));
+        assert_eq!(state.tick(), Some(200));
+        assert_eq!(state.len(), 1);
+        assert!(state.get("s1").is_some());
+        assert!(state.get("r1").is_none());
+
+        // 丢弃事件后也清空，等待重新订阅后的完整推送
+        assert!(state.apply_socket_event(&SocketEvent::Lagged { dropped: 3 }));
+        assert!(state.is_empty());
+    }
+}
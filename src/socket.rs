@@ -0,0 +1,864 @@
+//! Screeps websocket 客户端
+//!
+//! 连接服务器的 SockJS raw websocket 端点 `/socket/websocket`，使用 http 客户端中的 token
+//! 进行认证。连接断开后会自动重连、重新认证并恢复所有订阅，调用方只需要从 `Stream` 中读取事件。
+
+use std::{
+    collections::BTreeSet,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use futures_util::{SinkExt, Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::{
+    net::TcpStream,
+    sync::mpsc,
+    task::JoinHandle,
+    time::{Instant, interval_at, sleep},
+};
+use tokio_tungstenite::{
+    MaybeTlsStream, WebSocketStream, connect_async, tungstenite::protocol::Message,
+};
+
+use crate::{
+    config::ScreepsConfig,
+    error::{ScreepsError, ScreepsResult},
+    http_client::ScreepsHttpClient,
+    utils::{GZ_PREFIX, decode_zlib_data},
+};
+
+type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// websocket 订阅频道
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Channel {
+    /// 控制台输出，`user:<id>/console`
+    Console { user_id: String },
+    /// cpu 和内存使用，`user:<id>/cpu`
+    Cpu { user_id: String },
+    /// 房间对象变化，`room:<shard>/<room>`
+    Room { shard: String, room: String },
+    /// 地图视图，`roomMap2:<shard>/<room>`
+    RoomMap2 { shard: String, room: String },
+    /// 服务器消息，`server-message`
+    ServerMessage,
+    /// 其他未封装的频道
+    Custom(String),
+}
+
+impl Channel {
+    /// 频道在协议中的名称
+    pub fn name(&self) -> String {
+        match self {
+            Self::Console { user_id } => format!("user:{}/console", user_id),
+            Self::Cpu { user_id } => format!("user:{}/cpu", user_id),
+            Self::Room { shard, room } => format!("room:{}/{}", shard, room),
+            Self::RoomMap2 { shard, room } => format!("roomMap2:{}/{}", shard, room),
+            Self::ServerMessage => "server-message".to_string(),
+            Self::Custom(name) => name.clone(),
+        }
+    }
+
+    /// 根据协议中的频道名称解析频道，无法识别的返回 `Custom`
+    pub fn parse(name: &str) -> Self {
+        let shard_room = |rest: &str| {
+            rest.split_once('/')
+                .map(|(shard, room)| (shard.to_string(), room.to_string()))
+        };
+        if name == "server-message" {
+            return Self::ServerMessage;
+        }
+        if let Some(rest) = name.strip_prefix("user:") {
+            if let Some(user_id) = rest.strip_suffix("/console") {
+                return Self::Console {
+                    user_id: user_id.to_string(),
+                };
+            }
+            if let Some(user_id) = rest.strip_suffix("/cpu") {
+                return Self::Cpu {
+                    user_id: user_id.to_string(),
+                };
+            }
+        }
+        if let Some((shard, room)) = name.strip_prefix("room:").and_then(shard_room) {
+            return Self::Room { shard, room };
+        }
+        if let Some((shard, room)) = name.strip_prefix("roomMap2:").and_then(shard_room) {
+            return Self::RoomMap2 { shard, room };
+        }
+        Self::Custom(name.to_string())
+    }
+}
+
+/// 控制台频道的消息
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ConsoleMessages {
+    /// `console.log` 输出
+    #[serde(default)]
+    pub log: Vec<String>,
+    /// 控制台命令的执行结果
+    #[serde(default)]
+    pub results: Vec<String>,
+}
+
+/// 控制台频道事件
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ConsoleEvent {
+    pub messages: Option<ConsoleMessages>,
+    /// 代码运行错误
+    pub error: Option<String>,
+    pub shard: Option<String>,
+}
+
+/// cpu 频道事件
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CpuEvent {
+    /// 上一 tick 使用的 cpu
+    pub cpu: f64,
+    /// 内存大小，单位字节
+    pub memory: u64,
+}
+
+/// 房间频道事件，`objects` 为相对上一次推送的差异，值为 `null` 表示对象被删除
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RoomEvent {
+    #[serde(default)]
+    pub objects: serde_json::Map<String, Value>,
+    #[serde(rename = "gameTime")]
+    pub game_time: Option<u64>,
+    pub info: Option<Value>,
+    pub users: Option<Value>,
+    pub visual: Option<Value>,
+}
+
+/// websocket 事件
+#[derive(Debug, Clone)]
+pub enum SocketEvent {
+    /// 认证成功，每次重连成功后都会收到
+    Connected,
+    /// 连接断开，随后会自动重连，认证失败时不再重连
+    Disconnected {
+        reason: String,
+    },
+    /// 服务器当前时间
+    Time(u64),
+    Console {
+        user_id: String,
+        event: ConsoleEvent,
+    },
+    Cpu {
+        user_id: String,
+        event: CpuEvent,
+    },
+    Room {
+        shard: String,
+        room: String,
+        event: RoomEvent,
+    },
+    RoomMap2 {
+        shard: String,
+        room: String,
+        data: Value,
+    },
+    ServerMessage(Value),
+    /// 压缩的推送数据无法解压或解析，`data` 为原始消息
+    DecodeError {
+        error: String,
+        data: String,
+    },
+    /// 调用方读取太慢，事件缓冲区已满，丢弃了 `dropped` 个事件
+    ///
+    /// 之后会重新订阅所有房间频道，服务器会重新推送完整的房间数据
+    Lagged {
+        dropped: u64,
+    },
+    /// 未封装的频道或无法解析的数据
+    Other {
+        channel: String,
+        data: Value,
+    },
+}
+
+impl SocketEvent {
+    /// 根据频道名称和数据构造事件，数据格式不符时返回 `Other`
+    pub fn from_channel(channel: &str, data: Value) -> Self {
+        let other = |data: Value| Self::Other {
+            channel: channel.to_string(),
+            data,
+        };
+        match Channel::parse(channel) {
+            Channel::Console { user_id } => match serde_json::from_value(data.clone()) {
+                Ok(event) => Self::Console { user_id, event },
+                Err(_) => other(data),
+            },
+            Channel::Cpu { user_id } => match serde_json::from_value(data.clone()) {
+                Ok(event) => Self::Cpu { user_id, event },
+                Err(_) => other(data),
+            },
+            Channel::Room { shard, room } => match serde_json::from_value(data.clone()) {
+                Ok(event) => Self::Room { shard, room, event },
+                Err(_) => other(data),
+            },
+            Channel::RoomMap2 { shard, room } => Self::RoomMap2 { shard, room, data },
+            Channel::ServerMessage => Self::ServerMessage(data),
+            Channel::Custom(_) => other(data),
+        }
+    }
+}
+
+/// websocket 连接选项
+#[derive(Debug, Clone)]
+pub struct SocketOptions {
+    /// 发送心跳的间隔，超过两个间隔没有收到任何数据视为连接已断开
+    pub keepalive: Duration,
+    /// 首次重连的等待时间，之后每次翻倍
+    pub reconnect_delay: Duration,
+    /// 重连等待时间的上限
+    pub max_reconnect_delay: Duration,
+    /// 未读取事件的缓冲区大小，缓冲区满时丢弃新事件并发送 `SocketEvent::Lagged`
+    pub event_buffer: usize,
+    /// 未处理的订阅命令的缓冲区大小，缓冲区满时 `subscribe` 返回错误
+    pub command_buffer: usize,
+}
+
+impl Default for SocketOptions {
+    fn default() -> Self {
+        Self {
+            keepalive: Duration::from_secs(30),
+            reconnect_delay: Duration::from_secs(1),
+            max_reconnect_delay: Duration::from_secs(60),
+            event_buffer: 1024,
+            command_buffer: 64,
+        }
+    }
+}
+
+/// 服务器发来的一条协议消息
+#[derive(Debug, PartialEq)]
+enum Frame {
+    AuthOk(String),
+    AuthFailed,
+    Time(u64),
+    Event(String, Value),
+    /// SockJS 关闭帧
+    Close(String),
+    /// 压缩数据解码失败，(错误信息, 原始消息)
+    DecodeError(String, String),
+}
+
+/// 解析服务器发来的文本消息，兼容 SockJS 的 `o`/`h`/`a[...]`/`c[...]` 帧
+fn parse_frames(text: &str) -> Vec<Frame> {
+    match text {
+        "o" | "h" => return vec![],
+        _ => {}
+    }
+    if let Some(array) = text.strip_prefix('a').filter(|rest| rest.starts_with('[')) {
+        return serde_json::from_str::<Vec<String>>(array)
+            .map(|messages| messages.iter().flat_map(|m| parse_frames(m)).collect())
+            .unwrap_or_default();
+    }
+    if let Some(reason) = text.strip_prefix('c').filter(|rest| rest.starts_with('[')) {
+        return vec![Frame::Close(reason.to_string())];
+    }
+    if let Some(token) = text.strip_prefix("auth ok ") {
+        return vec![Frame::AuthOk(token.to_string())];
+    }
+    if text.starts_with("auth failed") {
+        return vec![Frame::AuthFailed];
+    }
+    if let Some(time) = text.strip_prefix("time ") {
+        return time.trim().parse().map(Frame::Time).into_iter().collect();
+    }
+    if text.starts_with(GZ_PREFIX) {
+        let frame = decode_zlib_data(text)
+            .and_then(|decoded| Ok(serde_json::from_str::<(String, Value)>(&decoded)?));
+        return match frame {
+            Ok((channel, data)) => vec![Frame::Event(channel, data)],
+            Err(error) => vec![Frame::DecodeError(error.to_string(), text.to_string())],
+        };
+    }
+    match serde_json::from_str::<(String, Value)>(text) {
+        Ok((channel, data)) => vec![Frame::Event(channel, data)],
+        // protocol、package 等消息不需要处理
+        Err(_) => vec![],
+    }
+}
+
+/// 客户端发给后台任务的命令
+enum Command {
+    Subscribe(String),
+    Unsubscribe(String),
+}
+
+/// 一次连接结束的原因
+enum ConnectionEnd {
+    /// 调用方已关闭，不再重连
+    Shutdown,
+    /// 认证失败，不再重连
+    AuthFailed,
+    /// 连接异常断开，需要重连
+    Lost(String),
+}
+
+/// Screeps websocket 客户端，实现了 `Stream<Item = SocketEvent>`
+///
+/// 被 drop 时会关闭连接
+pub struct ScreepsSocket {
+    commands: mpsc::Sender<Command>,
+    events: mpsc::Receiver<SocketEvent>,
+    task: JoinHandle<()>,
+}
+
+impl ScreepsSocket {
+    /// 使用 http 客户端的配置和 token 建立连接
+    pub async fn connect(http_client: &ScreepsHttpClient) -> ScreepsResult<Self> {
+        Self::connect_with_options(http_client, SocketOptions::default()).await
+    }
+
+    /// 使用指定选项建立连接，首次连接失败直接返回错误
+    pub async fn connect_with_options(
+        http_client: &ScreepsHttpClient,
+        options: SocketOptions,
+    ) -> ScreepsResult<Self> {
+        let token = http_client.token.lock().unwrap().clone();
+        let Some(token) = token.filter(|token| !token.is_empty()) else {
+            return Err(ScreepsError::Auth);
+        };
+        let url = build_socket_url(&http_client.config);
+        let (ws, _) = connect_async(url.as_str())
+            .await
+            .map_err(|e| ScreepsError::WebSocket(e.to_string()))?;
+
+        let (command_tx, command_rx) = mpsc::channel(options.command_buffer.max(1));
+        let (event_tx, event_rx) = mpsc::channel(options.event_buffer.max(1));
+        let worker = SocketWorker {
+            url,
+            token,
+            options,
+            subscriptions: BTreeSet::new(),
+            commands: command_rx,
+            events: event_tx,
+            dropped: 0,
+            resync: false,
+        };
+        let task = tokio::spawn(worker.run(ws));
+        Ok(Self {
+            commands: command_tx,
+            events: event_rx,
+            task,
+        })
+    }
+
+    /// 订阅频道，断线重连后会自动重新订阅
+    ///
+    /// 命令缓冲区已满或连接已关闭时返回 `ScreepsError::WebSocket`
+    pub fn subscribe(&self, channel: Channel) -> ScreepsResult<()> {
+        self.send_command(Command::Subscribe(channel.name()))
+    }
+
+    /// 订阅房间频道，配合 `RoomState` 维护房间的完整状态
+    pub fn subscribe_room(&self, shard: &str, room: &str) -> ScreepsResult<()> {
+        self.subscribe(Channel::Room {
+            shard: shard.to_string(),
+            room: room.to_string(),
+        })
+    }
+
+    /// 取消订阅频道
+    pub fn unsubscribe(&self, channel: Channel) -> ScreepsResult<()> {
+        self.send_command(Command::Unsubscribe(channel.name()))
+    }
+
+    fn send_command(&self, command: Command) -> ScreepsResult<()> {
+        self.commands.try_send(command).map_err(|e| match e {
+            mpsc::error::TrySendError::Full(_) => {
+                ScreepsError::WebSocket("command buffer is full".to_string())
+            }
+            mpsc::error::TrySendError::Closed(_) => {
+                ScreepsError::WebSocket("socket is closed".to_string())
+            }
+        })
+    }
+
+    /// 等待下一个事件，连接关闭后返回 `None`
+    pub async fn next_event(&mut self) -> Option<SocketEvent> {
+        self.events.recv().await
+    }
+}
+
+impl Stream for ScreepsSocket {
+    type Item = SocketEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.events.poll_recv(cx)
+    }
+}
+
+impl Drop for ScreepsSocket {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// 根据配置构造 websocket 地址
+pub fn build_socket_url(config: &ScreepsConfig) -> String {
+    let protocol = if config.secure { "wss" } else { "ws" };
+    format!("{}://{}/socket/websocket", protocol, config.host)
+}
+
+/// 后台连接任务，负责心跳、重连和恢复订阅
+struct SocketWorker {
+    url: String,
+    token: String,
+    options: SocketOptions,
+    subscriptions: BTreeSet<String>,
+    commands: mpsc::Receiver<Command>,
+    events: mpsc::Sender<SocketEvent>,
+    /// 缓冲区满时丢弃、还没有通过 `SocketEvent::Lagged` 报告的事件数量
+    dropped: u64,
+    /// 已经报告丢弃事件，需要重新订阅房间频道
+    resync: bool,
+}
+
+impl SocketWorker {
+    async fn run(mut self, ws: WsStream) {
+        let mut ws = Some(ws);
+        let mut delay = self.options.reconnect_delay;
+        loop {
+            let connection = match ws.take() {
+                Some(ws) => Ok(ws),
+                None => connect_async(self.url.as_str())
+                    .await
+                    .map(|(ws, _)| ws)
+                    .map_err(|e| e.to_string()),
+            };
+            let reason = match connection {
+                Ok(ws) => match self.serve(ws, &mut delay).await {
+                    ConnectionEnd::Shutdown => return,
+                    ConnectionEnd::AuthFailed => {
+                        self.emit(SocketEvent::Disconnected {
+                            reason: "auth failed".to_string(),
+                        });
+                        return;
+                    }
+                    ConnectionEnd::Lost(reason) => reason,
+                },
+                Err(reason) => reason,
+            };
+            if !self.emit(SocketEvent::Disconnected { reason }) {
+                return;
+            }
+
+            // 等待重连期间仍然接收订阅命令
+            let wait = sleep(delay);
+            tokio::pin!(wait);
+            loop {
+                tokio::select! {
+                    _ = &mut wait => break,
+                    command = self.commands.recv() => match command {
+                        Some(command) => self.apply_command(command),
+                        None => return,
+                    },
+                }
+            }
+            delay = (delay * 2).min(self.options.max_reconnect_delay);
+        }
+    }
+
+    /// 处理一次连接，直到连接断开或调用方关闭
+    async fn serve(&mut self, mut ws: WsStream, delay: &mut Duration) -> ConnectionEnd {
+        if let Err(e) = ws.send(Message::text(format!("auth {}", self.token))).await {
+            return ConnectionEnd::Lost(e.to_string());
+        }
+        let keepalive = self.options.keepalive;
+        let mut ticker = interval_at(Instant::now() + keepalive, keepalive);
+        let mut last_seen = Instant::now();
+        let mut authed = false;
+
+        loop {
+            tokio::select! {
+                message = ws.next() => {
+                    let text = match message {
+                        Some(Ok(Message::Text(text))) => text.to_string(),
+                        Some(Ok(Message::Close(frame))) => {
+                            return ConnectionEnd::Lost(
+                                frame.map(|f| f.reason.to_string()).unwrap_or_default(),
+                            );
+                        }
+                        Some(Ok(_)) => {
+                            last_seen = Instant::now();
+                            continue;
+                        }
+                        Some(Err(e)) => return ConnectionEnd::Lost(e.to_string()),
+                        None => return ConnectionEnd::Lost("connection closed".to_string()),
+                    };
+                    last_seen = Instant::now();
+                    for frame in parse_frames(&text) {
+                        match frame {
+                            Frame::AuthOk(token) => {
+                                authed = true;
+                                *delay = self.options.reconnect_delay;
+                                self.token = token;
+                                for channel in &self.subscriptions {
+                                    if let Err(e) =
+                                        ws.send(Message::text(format!("subscribe {}", channel))).await
+                                    {
+                                        return ConnectionEnd::Lost(e.to_string());
+                                    }
+                                }
+                                if !self.emit(SocketEvent::Connected) {
+                                    return ConnectionEnd::Shutdown;
+                                }
+                            }
+                            Frame::AuthFailed => return ConnectionEnd::AuthFailed,
+                            Frame::Close(reason) => return ConnectionEnd::Lost(reason),
+                            Frame::Time(time) => {
+                                if !self.emit(SocketEvent::Time(time)) {
+                                    return ConnectionEnd::Shutdown;
+                                }
+                            }
+                            Frame::Event(channel, data) => {
+                                if !self.emit(SocketEvent::from_channel(&channel, data)) {
+                                    return ConnectionEnd::Shutdown;
+                                }
+                            }
+                            Frame::DecodeError(error, data) => {
+                                if !self.emit(SocketEvent::DecodeError { error, data }) {
+                                    return ConnectionEnd::Shutdown;
+                                }
+                            }
+                        }
+                    }
+                    if authed && let Err(reason) = self.resync_rooms(&mut ws).await {
+                        return ConnectionEnd::Lost(reason);
+                    }
+                }
+                command = self.commands.recv() => {
+                    let Some(command) = command else {
+                        let _ = ws.close(None).await;
+                        return ConnectionEnd::Shutdown;
+                    };
+                    let message = match &command {
+                        Command::Subscribe(channel) => format!("subscribe {}", channel),
+                        Command::Unsubscribe(channel) => format!("unsubscribe {}", channel),
+                    };
+                    self.apply_command(command);
+                    // 未认证时只记录订阅，认证成功后统一发送
+                    if authed && let Err(e) = ws.send(Message::text(message)).await {
+                        return ConnectionEnd::Lost(e.to_string());
+                    }
+                }
+                _ = ticker.tick() => {
+                    if last_seen.elapsed() > keepalive * 2 {
+                        return ConnectionEnd::Lost("keepalive timeout".to_string());
+                    }
+                    if let Err(e) = ws.send(Message::Ping(Default::default())).await {
+                        return ConnectionEnd::Lost(e.to_string());
+                    }
+                }
+            }
+        }
+    }
+
+    fn apply_command(&mut self, command: Command) {
+        match command {
+            Command::Subscribe(channel) => {
+                self.subscriptions.insert(channel);
+            }
+            Command::Unsubscribe(channel) => {
+                self.subscriptions.remove(&channel);
+            }
+        }
+    }
+
+    /// 发送事件，调用方已关闭时返回 false
+    ///
+    /// 缓冲区满时不等待调用方读取，丢弃事件并在缓冲区有空位时先发送 `SocketEvent::Lagged`
+    fn emit(&mut self, event: SocketEvent) -> bool {
+        if self.dropped > 0 {
+            match self.events.try_send(SocketEvent::Lagged {
+                dropped: self.dropped,
+            }) {
+                Ok(()) => {
+                    self.dropped = 0;
+                    self.resync = true;
+                }
+                Err(mpsc::error::TrySendError::Full(_)) => {
+                    self.dropped += 1;
+                    return true;
+                }
+                Err(mpsc::error::TrySendError::Closed(_)) => return false,
+            }
+        }
+        match self.events.try_send(event) {
+            Ok(()) => true,
+            Err(mpsc::error::TrySendError::Full(_)) => {
+                self.dropped += 1;
+                true
+            }
+            Err(mpsc::error::TrySendError::Closed(_)) => false,
+        }
+    }
+
+    /// 丢弃事件后重新订阅房间频道，让服务器重新推送完整的房间数据
+    async fn resync_rooms(&mut self, ws: &mut WsStream) -> Result<(), String> {
+        if !std::mem::take(&mut self.resync) {
+            return Ok(());
+        }
+        for channel in self.subscriptions.iter().filter(|c| c.starts_with("room:")) {
+            for action in ["unsubscribe", "subscribe"] {
+                ws.send(Message::text(format!("{} {}", action, channel)))
+                    .await
+                    .map_err(|e| e.to_string())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::accept_async;
+
+    use super::*;
+
+    #[test]
+    fn test_channel_name() {
+        let channels = [
+            Channel::Console {
+                user_id: "u1".to_string(),
+            },
+            Channel::Cpu {
+                user_id: "u1".to_string(),
+            },
+            Channel::Room {
+                shard: "shard3".to_string(),
+                room: "E13S13".to_string(),
+            },
+            Channel::RoomMap2 {
+                shard: "shard3".to_string(),
+                room: "E13S13".to_string(),
+            },
+            Channel::ServerMessage,
+            Channel::Custom("user:u1/memory/stats".to_string()),
+        ];
+        for channel in channels {
+            assert_eq!(Channel::parse(&channel.name()), channel);
+        }
+        assert_eq!(
+            Channel::Room {
+                shard: "shard3".to_string(),
+                room: "E13S13".to_string(),
+            }
+            .name(),
+            "room:shard3/E13S13"
+        );
+    }
+
+    #[test]
+    fn test_parse_frames() {
+        assert_eq!(parse_frames("o"), vec![]);
+        assert_eq!(parse_frames("protocol 14"), vec![]);
+        assert_eq!(
+            parse_frames("time 1700000000"),
+            vec![Frame::Time(1700000000)]
+        );
+        assert_eq!(
+            parse_frames("auth ok abc"),
+            vec![Frame::AuthOk("abc".to_string())]
+        );
+        assert_eq!(parse_frames("auth failed"), vec![Frame::AuthFailed]);
+        assert_eq!(
+            parse_frames(r#"a["auth ok t1","[\"server-message\",{\"a\":1}]"]"#),
+            vec![
+                Frame::AuthOk("t1".to_string()),
+                Frame::Event("server-message".to_string(), json!({ "a": 1 })),
+            ]
+        );
+
+        // 服务器使用 zlib.deflate 压缩推送数据
+        let compressed = "gz:eJyLVirKz8+1Ks5ILEox1nc1NA42NFbSqVbKT8pKTS4pVrKqVko0BJEZmSCeoYFBba2OUnpibmpIZm4qUMDIuDYWAPlHFYQ=";
+        assert_eq!(
+            parse_frames(compressed),
+            vec![Frame::Event(
+                "room:shard3/E13S13".to_string(),
+                json!({ "objects": { "a1": { "hits": 100 } }, "gameTime": 123 })
+            )]
+        );
+        assert!(matches!(
+            parse_frames("gz:!!!").as_slice(),
+            [Frame::DecodeError(_, data)] if data == "gz:!!!"
+        ));
+    }
+
+    #[test]
+    fn test_event_from_channel() {
+        let event =
+            SocketEvent::from_channel("user:u1/cpu", json!({ "cpu": 12.5, "memory": 1024 }));
+        assert!(
+            matches!(event, SocketEvent::Cpu { user_id, event } if user_id == "u1" && event.memory == 1024)
+        );
+
+        let event = SocketEvent::from_channel(
+            "user:u1/console",
+            json!({ "messages": { "log": ["hello"], "results": [] }, "shard": "shard3" }),
+        );
+        match event {
+            SocketEvent::Console { event, .. } => {
+                assert_eq!(event.messages.unwrap().log, vec!["hello".to_string()])
+            }
+            _ => panic!("unexpected event {:?}", event),
+        }
+
+        let event = SocketEvent::from_channel("user:u1/cpu", json!("bad"));
+        assert!(matches!(event, SocketEvent::Other { .. }));
+    }
+
+    /// 模拟服务器：第一次连接推送一条事件后断开，第二次连接检查是否重新订阅
+    #[tokio::test]
+    async fn test_reconnect_and_resubscribe() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let mut tokens = vec![];
+            for round in 0..2 {
+                let (stream, _) = listener.accept().await.unwrap();
+                let mut ws = accept_async(stream).await.unwrap();
+                let auth = ws.next().await.unwrap().unwrap().into_text().unwrap();
+                tokens.push(auth.to_string());
+                ws.send(Message::text(format!("auth ok token{}", round + 1)))
+                    .await
+                    .unwrap();
+                let subscribe = ws.next().await.unwrap().unwrap().into_text().unwrap();
+                assert_eq!(subscribe.as_str(), "subscribe user:u1/cpu");
+                ws.send(Message::text(
+                    json!(["user:u1/cpu", { "cpu": round, "memory": 1 }]).to_string(),
+                ))
+                .await
+                .unwrap();
+                if round == 0 {
+                    ws.close(None).await.unwrap();
+                } else {
+                    let _ = ws.next().await;
+                }
+            }
+            tokens
+        });
+
+        let mut config = ScreepsConfig::default();
+        config.with_host(addr.to_string());
+        config.with_secure(false);
+        config.with_token("token0".to_string());
+        let http_client = ScreepsHttpClient::new(config);
+        let options = SocketOptions {
+            reconnect_delay: Duration::from_millis(10),
+            ..Default::default()
+        };
+        let mut socket = ScreepsSocket::connect_with_options(&http_client, options)
+            .await
+            .unwrap();
+        socket
+            .subscribe(Channel::Cpu {
+                user_id: "u1".to_string(),
+            })
+            .unwrap();
+
+        let mut cpu = vec![];
+        while cpu.len() < 2 {
+            match socket.next().await.unwrap() {
+                SocketEvent::Cpu { event, .. } => cpu.push(event.cpu),
+                SocketEvent::Connected | SocketEvent::Disconnected { .. } => {}
+                event => panic!("unexpected event {:?}", event),
+            }
+        }
+        assert_eq!(cpu, vec![0.0, 1.0]);
+        drop(socket);
+
+        let tokens = server.await.unwrap();
+        assert_eq!(tokens, vec!["auth token0", "auth token1"]);
+    }
+
+    #[tokio::test]
+    async fn test_slow_consumer_lags_and_resyncs_rooms() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (resume_tx, resume_rx) = tokio::sync::oneshot::channel();
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws = accept_async(stream).await.unwrap();
+            ws.next().await.unwrap().unwrap();
+            ws.send(Message::text("auth ok token1")).await.unwrap();
+            let subscribe = ws.next().await.unwrap().unwrap().into_text().unwrap();
+            assert_eq!(subscribe.as_str(), "subscribe room:shard3/E1N1");
+            let room_event = |time: u64| {
+                Message::text(
+                    json!(["room:shard3/E1N1", { "objects": {}, "gameTime": time }]).to_string(),
+                )
+            };
+            for time in 0..5 {
+                ws.send(room_event(time)).await.unwrap();
+            }
+            // 调用方读取缓冲区后再推送，丢弃的事件数量会先于这次推送报告
+            resume_rx.await.unwrap();
+            ws.send(room_event(5)).await.unwrap();
+            let mut resync = vec![];
+            for _ in 0..2 {
+                resync.push(
+                    ws.next()
+                        .await
+                        .unwrap()
+                        .unwrap()
+                        .into_text()
+                        .unwrap()
+                        .to_string(),
+                );
+            }
+            resync
+        });
+
+        let mut config = ScreepsConfig::default();
+        config.with_host(addr.to_string());
+        config.with_secure(false);
+        config.with_token("token0".to_string());
+        let http_client = ScreepsHttpClient::new(config);
+        let options = SocketOptions {
+            event_buffer: 2,
+            ..Default::default()
+        };
+        let mut socket = ScreepsSocket::connect_with_options(&http_client, options)
+            .await
+            .unwrap();
+        socket.subscribe_room("shard3", "E1N1").unwrap();
+
+        // 等待缓冲区被填满
+        while socket.events.len() < 2 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(matches!(socket.next().await, Some(SocketEvent::Connected)));
+        assert!(matches!(
+            socket.next().await,
+            Some(SocketEvent::Room { event, .. }) if event.game_time == Some(0)
+        ));
+        resume_tx.send(()).unwrap();
+        assert!(matches!(
+            socket.next().await,
+            Some(SocketEvent::Lagged { dropped: 4 })
+        ));
+        assert!(matches!(
+            socket.next().await,
+            Some(SocketEvent::Room { event, .. }) if event.game_time == Some(5)
+        ));
+
+        let resync = server.await.unwrap();
+        assert_eq!(
+            resync,
+            vec!["unsubscribe room:shard3/E1N1", "subscribe room:shard3/E1N1"]
+        );
+    }
+}
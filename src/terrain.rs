@@ -0,0 +1,345 @@
+//! 房间地形网格
+//!
+//! 将 `get_room_terrain_encoded` 返回的 2500 位数字字符串或 `get_room_terrain` 返回的稀疏地形列表
+//! 解码为 50x50 的 `TerrainGrid`。
+
+use std::fmt;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de};
+
+use crate::{
+    error::{ScreepsError, ScreepsResult},
+    types::{EncodedRoomTerrain, RoomTerrain},
+};
+
+/// 房间边长
+pub const ROOM_SIZE: usize = 50;
+/// 房间格子数量
+pub const ROOM_AREA: usize = ROOM_SIZE * ROOM_SIZE;
+/// 压缩后的字节数，每个格子占 2 位
+pub const PACKED_TERRAIN_SIZE: usize = ROOM_AREA / 4;
+
+/// 地形类型
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Terrain {
+    #[default]
+    Plain,
+    Swamp,
+    Wall,
+}
+
+impl Terrain {
+    /// 根据编码字符解析地形，0: plain, 1: wall, 2: swamp, 3: wall
+    pub fn from_digit(digit: char) -> Option<Self> {
+        match digit {
+            '0' => Some(Self::Plain),
+            '1' | '3' => Some(Self::Wall),
+            '2' => Some(Self::Swamp),
+            _ => None,
+        }
+    }
+
+    /// 编码字符
+    pub fn to_digit(self) -> char {
+        match self {
+            Self::Plain => '0',
+            Self::Wall => '1',
+            Self::Swamp => '2',
+        }
+    }
+
+    /// 根据 `RoomTerrain.type` 解析地形
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "plain" => Some(Self::Plain),
+            "swamp" => Some(Self::Swamp),
+            "wall" => Some(Self::Wall),
+            _ => None,
+        }
+    }
+
+    /// 2 位压缩值
+    fn to_bits(self) -> u8 {
+        match self {
+            Self::Plain => 0,
+            Self::Wall => 1,
+            Self::Swamp => 2,
+        }
+    }
+
+    fn from_bits(bits: u8) -> Option<Self> {
+        match bits {
+            0 => Some(Self::Plain),
+            1 => Some(Self::Wall),
+            2 => Some(Self::Swamp),
+            _ => None,
+        }
+    }
+}
+
+/// 各种地形的格子数量
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TerrainCounts {
+    pub plain: usize,
+    pub swamp: usize,
+    pub wall: usize,
+}
+
+/// 50x50 的房间地形，按从左到右、从上到下的顺序存储
+///
+/// 序列化为与 `EncodedRoomTerrain.terrain` 相同的 2500 位数字字符串。
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct TerrainGrid {
+    tiles: Box<[Terrain; ROOM_AREA]>,
+}
+
+impl TerrainGrid {
+    /// 全部为平原的地形
+    pub fn new() -> Self {
+        Self {
+            tiles: Box::new([Terrain::Plain; ROOM_AREA]),
+        }
+    }
+
+    /// 解码 2500 位数字字符串
+    pub fn from_encoded(encoded: &str) -> ScreepsResult<Self> {
+        if encoded.len() != ROOM_AREA {
+            return Err(ScreepsError::Decode(format!(
+                "terrain length {} is not {}",
+                encoded.len(),
+                ROOM_AREA
+            )));
+        }
+        let mut grid = Self::new();
+        for (tile, digit) in grid.tiles.iter_mut().zip(encoded.chars()) {
+            *tile = Terrain::from_digit(digit)
+                .ok_or_else(|| ScreepsError::Decode(format!("invalid terrain digit {}", digit)))?;
+        }
+        Ok(grid)
+    }
+
+    /// 根据稀疏的地形列表构造，列表中没有的格子为平原
+    pub fn from_tiles(tiles: &[RoomTerrain]) -> ScreepsResult<Self> {
+        let mut grid = Self::new();
+        for tile in tiles {
+            let terrain = Terrain::from_name(&tile.r#type).ok_or_else(|| {
+                ScreepsError::Decode(format!("invalid terrain type {}", tile.r#type))
+            })?;
+            let (x, y) = match (usize::try_from(tile.x), usize::try_from(tile.y)) {
+                (Ok(x), Ok(y)) if x < ROOM_SIZE && y < ROOM_SIZE => (x, y),
+                _ => {
+                    return Err(ScreepsError::Decode(format!(
+                        "terrain position ({}, {}) out of room",
+                        tile.x, tile.y
+                    )));
+                }
+            };
+            grid.set(x, y, terrain);
+        }
+        Ok(grid)
+    }
+
+    /// 解压 `to_bytes` 的结果
+    pub fn from_bytes(bytes: &[u8]) -> ScreepsResult<Self> {
+        if bytes.len() != PACKED_TERRAIN_SIZE {
+            return Err(ScreepsError::Decode(format!(
+                "packed terrain length {} is not {}",
+                bytes.len(),
+                PACKED_TERRAIN_SIZE
+            )));
+        }
+        let mut grid = Self::new();
+        for (i, tile) in grid.tiles.iter_mut().enumerate() {
+            let bits = (bytes[i / 4] >> (i % 4 * 2)) & 0b11;
+            *tile = Terrain::from_bits(bits)
+                .ok_or_else(|| ScreepsError::Decode(format!("invalid terrain bits {}", bits)))?;
+        }
+        Ok(grid)
+    }
+
+    /// 获取指定位置的地形，坐标超出 0..50 时 panic
+    pub fn get(&self, x: usize, y: usize) -> Terrain {
+        self.tiles[Self::index(x, y)]
+    }
+
+    /// 设置指定位置的地形，坐标超出 0..50 时 panic
+    pub fn set(&mut self, x: usize, y: usize, terrain: Terrain) {
+        self.tiles[Self::index(x, y)] = terrain;
+    }
+
+    fn index(x: usize, y: usize) -> usize {
+        assert!(
+            x < ROOM_SIZE && y < ROOM_SIZE,
+            "position ({}, {}) out of room",
+            x,
+            y
+        );
+        y * ROOM_SIZE + x
+    }
+
+    /// 按从左到右、从上到下的顺序遍历所有格子，返回 (x, y, 地形)
+    pub fn iter(&self) -> impl Iterator<Item = (usize, usize, Terrain)> + '_ {
+        self.tiles
+            .iter()
+            .enumerate()
+            .map(|(i, terrain)| (i % ROOM_SIZE, i / ROOM_SIZE, *terrain))
+    }
+
+    /// 指定地形的格子数量
+    pub fn count(&self, terrain: Terrain) -> usize {
+        self.tiles.iter().filter(|tile| **tile == terrain).count()
+    }
+
+    /// 各种地形的格子数量
+    pub fn counts(&self) -> TerrainCounts {
+        let mut counts = TerrainCounts::default();
+        for tile in self.tiles.iter() {
+            match tile {
+                Terrain::Plain => counts.plain += 1,
+                Terrain::Swamp => counts.swamp += 1,
+                Terrain::Wall => counts.wall += 1,
+            }
+        }
+        counts
+    }
+
+    /// 编码为 2500 位数字字符串
+    pub fn to_encoded(&self) -> String {
+        self.tiles.iter().map(|tile| tile.to_digit()).collect()
+    }
+
+    /// 压缩为 625 字节，每个格子占 2 位
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![0; PACKED_TERRAIN_SIZE];
+        for (i, tile) in self.tiles.iter().enumerate() {
+            bytes[i / 4] |= tile.to_bits() << (i % 4 * 2);
+        }
+        bytes
+    }
+}
+
+impl Default for TerrainGrid {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for TerrainGrid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let counts = self.counts();
+        f.debug_struct("TerrainGrid")
+            .field("plain", &counts.plain)
+            .field("swamp", &counts.swamp)
+            .field("wall", &counts.wall)
+            .finish()
+    }
+}
+
+impl TryFrom<&EncodedRoomTerrain> for TerrainGrid {
+    type Error = ScreepsError;
+
+    fn try_from(terrain: &EncodedRoomTerrain) -> Result<Self, Self::Error> {
+        Self::from_encoded(&terrain.terrain)
+    }
+}
+
+impl Serialize for TerrainGrid {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_encoded())
+    }
+}
+
+impl<'de> Deserialize<'de> for TerrainGrid {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        Self::from_encoded(&encoded).map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_encoded() -> String {
+        let mut encoded = "0".repeat(ROOM_AREA);
+        // (1, 0) 为墙，(2, 1) 为沼泽，(49, 49) 为另一种编码的墙
+        encoded.replace_range(1..2, "1");
+        encoded.replace_range(52..53, "2");
+        encoded.replace_range(2499..2500, "3");
+        encoded
+    }
+
+    #[test]
+    fn test_from_encoded() {
+        let grid = TerrainGrid::from_encoded(&sample_encoded()).unwrap();
+        assert_eq!(grid.get(0, 0), Terrain::Plain);
+        assert_eq!(grid.get(1, 0), Terrain::Wall);
+        assert_eq!(grid.get(2, 1), Terrain::Swamp);
+        assert_eq!(grid.get(49, 49), Terrain::Wall);
+        assert_eq!(
+            grid.counts(),
+            TerrainCounts {
+                plain: 2497,
+                swamp: 1,
+                wall: 2
+            }
+        );
+        assert_eq!(grid.count(Terrain::Wall), 2);
+        assert_eq!(grid.iter().nth(52), Some((2, 1, Terrain::Swamp)));
+
+        assert!(TerrainGrid::from_encoded("0").is_err());
+        assert!(TerrainGrid::from_encoded(&"4".repeat(ROOM_AREA)).is_err());
+    }
+
+    #[test]
+    fn test_from_tiles() {
+        let tiles = vec![
+            RoomTerrain {
+                room: "E13S13".to_string(),
+                x: 1,
+                y: 0,
+                r#type: "wall".to_string(),
+            },
+            RoomTerrain {
+                room: "E13S13".to_string(),
+                x: 2,
+                y: 1,
+                r#type: "swamp".to_string(),
+            },
+            RoomTerrain {
+                room: "E13S13".to_string(),
+                x: 49,
+                y: 49,
+                r#type: "wall".to_string(),
+            },
+        ];
+        let grid = TerrainGrid::from_tiles(&tiles).unwrap();
+        assert_eq!(grid, TerrainGrid::from_encoded(&sample_encoded()).unwrap());
+
+        let out_of_room = RoomTerrain {
+            room: "E13S13".to_string(),
+            x: 50,
+            y: 0,
+            r#type: "wall".to_string(),
+        };
+        assert!(TerrainGrid::from_tiles(&[out_of_room]).is_err());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let grid = TerrainGrid::from_encoded(&sample_encoded()).unwrap();
+        let bytes = grid.to_bytes();
+        assert_eq!(bytes.len(), PACKED_TERRAIN_SIZE);
+        assert_eq!(TerrainGrid::from_bytes(&bytes).unwrap(), grid);
+        assert!(TerrainGrid::from_bytes(&bytes[1..]).is_err());
+
+        // 编码为 3 的墙统一编码为 1
+        let encoded = grid.to_encoded();
+        assert_eq!(&encoded[2499..], "1");
+        let json = serde_json::to_string(&grid).unwrap();
+        assert_eq!(json, format!("\"{}\"", encoded));
+        let decoded: TerrainGrid = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, grid);
+    }
+}
@@ -0,0 +1,217 @@
+//! 房间地形的本地缓存
+//!
+//! 地形不会变化，但 `/game/room-terrain` 每小时只能请求 360 次。`TerrainCache` 将解码后的地形
+//! 按 `<目录>/<shard>/<房间名>` 保存为 625 字节的压缩文件，进程重启后仍然有效。
+
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+    process,
+    sync::{
+        Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use crate::{
+    error::{ScreepsError, ScreepsResult},
+    room_name::RoomName,
+    terrain::TerrainGrid,
+};
+
+/// 临时文件序号，保证同一进程内并发写入时临时文件名不重复
+static TMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// 地形缓存，先查内存再查文件
+#[derive(Debug)]
+pub struct TerrainCache {
+    dir: PathBuf,
+    memory: Mutex<HashMap<(String, RoomName), TerrainGrid>>,
+}
+
+impl TerrainCache {
+    /// 参数：
+    /// - dir: 缓存目录，不存在时会在第一次写入时创建
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            memory: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 缓存目录
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// 获取缓存的地形，没有缓存时返回 `None`
+    pub fn get(&self, shard: &str, room: RoomName) -> ScreepsResult<Option<TerrainGrid>> {
+        let key = (shard.to_string(), room);
+        if let Some(grid) = self.memory.lock().unwrap().get(&key) {
+            return Ok(Some(grid.clone()));
+        }
+        let bytes = match fs::read(self.path(shard, room)?) {
+            Ok(bytes) => bytes,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(error) => return Err(error.into()),
+        };
+        let grid = TerrainGrid::from_bytes(&bytes)?;
+        self.memory.lock().unwrap().insert(key, grid.clone());
+        Ok(Some(grid))
+    }
+
+    /// 是否已缓存，只检查文件是否存在，不检查内容能否解码
+    pub fn contains(&self, shard: &str, room: RoomName) -> ScreepsResult<bool> {
+        if self
+            .memory
+            .lock()
+            .unwrap()
+            .contains_key(&(shard.to_string(), room))
+        {
+            return Ok(true);
+        }
+        Ok(self.path(shard, room)?.is_file())
+    }
+
+    /// 写入缓存，先写临时文件再重命名，避免进程中断时留下不完整的文件
+    ///
+    /// 临时文件名包含进程 id 和序号，多个进程或任务同时写入同一房间时不会互相覆盖
+    pub fn insert(&self, shard: &str, room: RoomName, grid: &TerrainGrid) -> ScreepsResult<()> {
+        let path = self.path(shard, room)?;
+        fs::create_dir_all(self.dir.join(shard))?;
+        let tmp_path = self.shard_dir(shard)?.join(format!(
+            ".{}.{}.{}.tmp",
+            room,
+            process::id(),
+            TMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::write(&tmp_path, grid.to_bytes())?;
+        if let Err(error) = fs::rename(&tmp_path, &path) {
+            let _ = fs::remove_file(&tmp_path);
+            return Err(error.into());
+        }
+        self.memory
+            .lock()
+            .unwrap()
+            .insert((shard.to_string(), room), grid.clone());
+        Ok(())
+    }
+
+    /// 读取指定 shard 所有已缓存的地形
+    pub fn load_shard(&self, shard: &str) -> ScreepsResult<HashMap<RoomName, TerrainGrid>> {
+        let shard_dir = self.shard_dir(shard)?;
+        let entries = match fs::read_dir(&shard_dir) {
+            Ok(entries) => entries,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(HashMap::new()),
+            Err(error) => return Err(error.into()),
+        };
+        let mut grids = HashMap::new();
+        for entry in entries {
+            let file_name = entry?.file_name();
+            // 跳过临时文件等其他文件
+            let Some(room) = file_name.to_str().and_then(|name| name.parse().ok()) else {
+                continue;
+            };
+            if let Some(grid) = self.get(shard, room)? {
+                grids.insert(room, grid);
+            }
+        }
+        Ok(grids)
+    }
+
+    fn shard_dir(&self, shard: &str) -> ScreepsResult<PathBuf> {
+        let valid = !shard.is_empty()
+            && shard
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(ScreepsError::InvalidParam(format!(
+                "invalid shard name: {}",
+                shard
+            )));
+        }
+        Ok(self.dir.join(shard))
+    }
+
+    fn path(&self, shard: &str, room: RoomName) -> ScreepsResult<PathBuf> {
+        Ok(self.shard_dir(shard)?.join(room.to_string()))
+    }
+}
+
+/// `prefetch_shard_terrain` 的结果
+#[derive(Debug, Default)]
+pub struct TerrainPrefetchReport {
+    /// 已经缓存、没有发送请求的房间
+    pub cached: Vec<RoomName>,
+    /// 本次请求并写入缓存的房间
+    pub fetched: Vec<RoomName>,
+    /// 请求失败的房间
+    pub failed: Vec<(RoomName, ScreepsError)>,
+    /// 请求次数用完、没有处理的房间，可以在限速重置后再次预取
+    pub pending: Vec<RoomName>,
+}
+
+impl TerrainPrefetchReport {
+    /// 是否所有房间都已缓存
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_empty() && self.pending.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::terrain::Terrain;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("terrain-cache-{}-{}", name, std::process::id()))
+    }
+
+    #[test]
+    fn test_insert_and_reload() {
+        let dir = temp_dir("reload");
+        let room: RoomName = "E13S13".parse().unwrap();
+        let mut grid = TerrainGrid::new();
+        grid.set(3, 4, Terrain::Wall);
+
+        let cache = TerrainCache::new(&dir);
+        assert!(cache.get("shard3", room).unwrap().is_none());
+        assert!(!cache.contains("shard3", room).unwrap());
+        cache.insert("shard3", room, &grid).unwrap();
+
+        // 新的缓存实例从文件读取
+        let cache = TerrainCache::new(&dir);
+        assert!(cache.contains("shard3", room).unwrap());
+        assert_eq!(cache.get("shard3", room).unwrap(), Some(grid.clone()));
+        assert!(cache.get("shard0", room).unwrap().is_none());
+        let shard = cache.load_shard("shard3").unwrap();
+        assert_eq!(shard.len(), 1);
+        assert_eq!(shard[&room], grid);
+        assert!(cache.load_shard("shard1").unwrap().is_empty());
+        // 临时文件已经重命名，目录中只有缓存文件
+        assert_eq!(fs::read_dir(dir.join("shard3")).unwrap().count(), 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_invalid_files() {
+        let dir = temp_dir("invalid");
+        let room: RoomName = "W1N1".parse().unwrap();
+        fs::create_dir_all(dir.join("shard3")).unwrap();
+        fs::write(dir.join("shard3").join("W1N1"), b"broken").unwrap();
+
+        let cache = TerrainCache::new(&dir);
+        assert!(matches!(
+            cache.get("shard3", room),
+            Err(ScreepsError::Decode(_))
+        ));
+        assert!(matches!(
+            cache.get("../shard3", room),
+            Err(ScreepsError::InvalidParam(_))
+        ));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,240 @@
+//! http 传输层
+//!
+//! `ScreepsHttpClient` 通过 `Transport` 发送请求，默认使用基于 reqwest 的 `ReqwestTransport`。
+//! 测试时可以换成 `MockTransport`，返回预设的响应和响应头，不需要访问网络。
+
+use std::{
+    collections::{HashMap, VecDeque},
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use bytes::Bytes;
+use reqwest::{
+    Client, Url,
+    header::{HeaderMap, HeaderName, HeaderValue},
+};
+use serde::Serialize;
+
+use crate::{error::ScreepsResult, http_client::Method};
+
+/// 返回 `Send` future 的 box 类型
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// 发送给传输层的请求
+#[derive(Debug, Clone)]
+pub struct TransportRequest {
+    pub method: Method,
+    /// 完整的请求地址，GET 请求的参数已拼接在地址中
+    pub url: String,
+    pub headers: HeaderMap,
+    /// POST 请求的 json 内容
+    pub body: Option<Bytes>,
+}
+
+impl TransportRequest {
+    /// 去掉 `/api` 前缀后的请求路径
+    pub fn path(&self) -> String {
+        let path = Url::parse(&self.url)
+            .map(|url| url.path().to_string())
+            .unwrap_or_default();
+        match path.strip_prefix("/api") {
+            Some(path) => path.to_string(),
+            None => path,
+        }
+    }
+
+    /// 获取指定的 GET 请求参数
+    pub fn query(&self, key: &str) -> Option<String> {
+        Url::parse(&self.url)
+            .ok()?
+            .query_pairs()
+            .find_map(|(k, v)| if k == key { Some(v.to_string()) } else { None })
+    }
+
+    /// 将 POST 请求内容解析为 json
+    pub fn json(&self) -> Option<serde_json::Value> {
+        serde_json::from_slice(self.body.as_ref()?).ok()
+    }
+}
+
+/// 传输层返回的响应
+#[derive(Debug, Clone)]
+pub struct TransportResponse {
+    /// http 状态码
+    pub status: u16,
+    pub headers: HeaderMap,
+    pub body: Bytes,
+}
+
+/// http 传输层
+pub trait Transport: Send + Sync {
+    /// 发送请求
+    fn send(&self, request: TransportRequest) -> BoxFuture<'_, ScreepsResult<TransportResponse>>;
+}
+
+impl<T: Transport + ?Sized> Transport for Arc<T> {
+    fn send(&self, request: TransportRequest) -> BoxFuture<'_, ScreepsResult<TransportResponse>> {
+        (**self).send(request)
+    }
+}
+
+/// 基于 reqwest 的传输层
+pub struct ReqwestTransport {
+    /// 请求客户端
+    pub client: Client,
+}
+
+impl ReqwestTransport {
+    /// 创建传输层
+    /// 参数：
+    /// - timeout: 请求超时时间，单位 s
+    pub fn new(timeout: u64) -> Self {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(timeout))
+            .build()
+            .unwrap();
+        Self { client }
+    }
+}
+
+impl Transport for ReqwestTransport {
+    fn send(&self, request: TransportRequest) -> BoxFuture<'_, ScreepsResult<TransportResponse>> {
+        Box::pin(async move {
+            let request_builder = match request.method {
+                Method::Get => self.client.get(request.url),
+                Method::Post => self.client.post(request.url),
+            }
+            .headers(request.headers);
+            let request_builder = match request.body {
+                Some(body) => request_builder.body(body),
+                None => request_builder,
+            };
+            let response = request_builder.send().await?;
+            let status = response.status().as_u16();
+            let headers = response.headers().clone();
+            let body = response.bytes().await?;
+            Ok(TransportResponse {
+                status,
+                headers,
+                body,
+            })
+        })
+    }
+}
+
+/// 预设的响应
+#[derive(Debug, Clone)]
+pub struct MockResponse {
+    pub status: u16,
+    pub headers: HeaderMap,
+    pub body: Bytes,
+}
+
+impl MockResponse {
+    /// 状态码为 200 的 json 响应
+    pub fn json<T: Serialize>(body: &T) -> Self {
+        Self::status(200).body(serde_json::to_vec(body).unwrap())
+    }
+
+    /// 指定状态码的空响应
+    pub fn status(status: u16) -> Self {
+        Self {
+            status,
+            headers: HeaderMap::new(),
+            body: Bytes::new(),
+        }
+    }
+
+    /// 设置响应内容
+    pub fn body(mut self, body: impl Into<Bytes>) -> Self {
+        self.body = body.into();
+        self
+    }
+
+    /// 添加响应头
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.insert(
+            HeaderName::from_bytes(name.as_bytes()).unwrap(),
+            HeaderValue::from_str(value).unwrap(),
+        );
+        self
+    }
+
+    /// 添加 `x-ratelimit-*` 响应头
+    pub fn rate_limit(self, limit: i32, remaining: i32, reset: u64) -> Self {
+        self.header("x-ratelimit-limit", &limit.to_string())
+            .header("x-ratelimit-remaining", &remaining.to_string())
+            .header("x-ratelimit-reset", &reset.to_string())
+    }
+}
+
+/// 内存中的模拟传输层，按请求方法和路径返回预设的响应，并记录所有请求
+///
+/// 同一路径预设多个响应时按顺序返回，最后一个响应会被重复使用；没有预设的路径返回 404。
+#[derive(Default)]
+pub struct MockTransport {
+    responses: Mutex<HashMap<(Method, String), VecDeque<MockResponse>>>,
+    requests: Mutex<Vec<TransportRequest>>,
+}
+
+impl MockTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 为指定请求方法和路径追加一个预设响应
+    /// 参数：
+    /// - method: 请求方法，GET or POST
+    /// - path: 请求路径，不包含 `/api` 前缀
+    /// - response: 预设响应
+    pub fn on(&self, method: Method, path: &str, response: MockResponse) -> &Self {
+        self.responses
+            .lock()
+            .unwrap()
+            .entry((method, path.to_string()))
+            .or_default()
+            .push_back(response);
+        self
+    }
+
+    /// 已收到的所有请求
+    pub fn requests(&self) -> Vec<TransportRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// 指定路径收到的请求
+    pub fn requests_to(&self, path: &str) -> Vec<TransportRequest> {
+        self.requests()
+            .into_iter()
+            .filter(|request| request.path() == path)
+            .collect()
+    }
+}
+
+impl Transport for MockTransport {
+    fn send(&self, request: TransportRequest) -> BoxFuture<'_, ScreepsResult<TransportResponse>> {
+        let key = (request.method.clone(), request.path());
+        self.requests.lock().unwrap().push(request);
+        let response = {
+            let mut responses = self.responses.lock().unwrap();
+            match responses.get_mut(&key) {
+                Some(queue) if queue.len() > 1 => queue.pop_front(),
+                Some(queue) => queue.front().cloned(),
+                None => None,
+            }
+        };
+        let response = response.unwrap_or_else(|| {
+            MockResponse::status(404).body(format!(r#"{{"error":"no mock for {}"}}"#, key.1))
+        });
+        Box::pin(async move {
+            Ok(TransportResponse {
+                status: response.status,
+                headers: response.headers,
+                body: response.body,
+            })
+        })
+    }
+}
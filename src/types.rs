@@ -1,6 +1,13 @@
 use std::{collections::HashMap, str};
 
-use serde::{Deserialize, Serialize};
+use base64::{Engine, prelude::BASE64_STANDARD};
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de};
+use serde_json::{Map, Value};
+
+use crate::{
+    creep::{BodyPartType, CreepBodyPart},
+    resource::Store,
+};
 
 /// 头像数据
 #[derive(Serialize, Deserialize, Debug)]
@@ -176,9 +183,12 @@ pub struct BaseObject {
     pub room: String,
 }
 
-// 首先，我们需要定义一个枚举来表示所有可能的对象类型
+/// 房间对象，按 `type` 字段区分类型
+///
+/// 每个对象结构体的 `extra` 字段保存没有建模的字段，未知类型的对象保存为 `RoomObject::Unknown`，
+/// 重新序列化时这些数据不会丢失。已知类型缺少必需字段时返回错误，以便发现服务器数据结构的变化。
 #[derive(Debug, Serialize, Deserialize)]
-#[serde(tag = "type")]
+#[serde(tag = "type", remote = "Self")]
 pub enum RoomObject {
     #[serde(rename = "source")]
     Source(Source),
@@ -237,9 +247,117 @@ pub enum RoomObject {
     #[serde(rename = "powerCreep")]
     PowerCreep(PowerCreep),
 
-    // 对于未知的对象类型，我们可以使用未匹配的变体
-    #[serde(other)]
-    Unknown,
+    #[serde(rename = "link")]
+    Link(Link),
+
+    #[serde(rename = "container")]
+    Container(Container),
+
+    #[serde(rename = "portal")]
+    Portal(Portal),
+
+    #[serde(rename = "keeperLair")]
+    KeeperLair(KeeperLair),
+
+    #[serde(rename = "invaderCore")]
+    InvaderCore(InvaderCore),
+
+    #[serde(rename = "ruin")]
+    Ruin(Ruin),
+
+    #[serde(rename = "tombstone")]
+    Tombstone(Tombstone),
+
+    #[serde(rename = "deposit")]
+    Deposit(Deposit),
+
+    #[serde(rename = "powerBank")]
+    PowerBank(PowerBank),
+
+    #[serde(rename = "constructionSite")]
+    ConstructionSite(ConstructionSite),
+
+    #[serde(rename = "nuke")]
+    Nuke(Nuke),
+
+    #[serde(rename = "flag")]
+    Flag(Flag),
+
+    /// 未知类型的对象
+    #[serde(skip)]
+    Unknown(UnknownObject),
+}
+
+/// `RoomObject` 中已经建模的对象类型
+pub const ROOM_OBJECT_TYPES: [&str; 31] = [
+    "source",
+    "mineral",
+    "constructedWall",
+    "road",
+    "controller",
+    "spawn",
+    "extension",
+    "storage",
+    "tower",
+    "rampart",
+    "extractor",
+    "terminal",
+    "observer",
+    "powerSpawn",
+    "nuker",
+    "factory",
+    "lab",
+    "creep",
+    "powerCreep",
+    "link",
+    "container",
+    "portal",
+    "keeperLair",
+    "invaderCore",
+    "ruin",
+    "tombstone",
+    "deposit",
+    "powerBank",
+    "constructionSite",
+    "nuke",
+    "flag",
+];
+
+impl Serialize for RoomObject {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            // 未知对象的数据中已经包含 `type`，直接序列化
+            RoomObject::Unknown(unknown) => unknown.serialize(serializer),
+            object => RoomObject::serialize(object, serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for RoomObject {
+    /// 先检查 `type`，只有未知类型才保存为 `RoomObject::Unknown`
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = Value::deserialize(deserializer)?;
+        let known = match value.get("type") {
+            Some(Value::String(object_type)) => ROOM_OBJECT_TYPES.contains(&object_type.as_str()),
+            _ => return Err(de::Error::missing_field("type")),
+        };
+        if known {
+            RoomObject::deserialize(value).map_err(de::Error::custom)
+        } else {
+            UnknownObject::deserialize(value)
+                .map(RoomObject::Unknown)
+                .map_err(de::Error::custom)
+        }
+    }
+}
+
+/// 未知类型的房间对象，保留原始的 `type` 和全部数据
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct UnknownObject {
+    #[serde(rename = "type")]
+    pub object_type: String,
+    #[serde(flatten)]
+    pub data: Map<String, Value>,
 }
 
 /// Source 对象
@@ -256,6 +374,8 @@ pub struct Source {
     pub invader_harvested: i32,
     #[serde(rename = "nextRegenerationTime")]
     pub next_regeneration_time: Option<u64>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 /// Mineral 对象
@@ -269,6 +389,8 @@ pub struct Mineral {
     pub mineral_amount: i32,
     #[serde(rename = "nextRegenerationTime")]
     pub next_regeneration_time: Option<u64>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 /// ConstructedWall 对象
@@ -283,6 +405,8 @@ pub struct ConstructedWall {
     pub notify_when_attacked: Option<bool>,
     #[serde(rename = "decayTime")]
     pub decay_time: Option<DecayTime>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 /// 消失时间
@@ -303,6 +427,8 @@ pub struct Road {
     pub notify_when_attacked: bool,
     #[serde(rename = "nextDecayTime")]
     pub next_decay_time: Option<u64>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 /// Controller 对象的 Reservation 字段
@@ -361,6 +487,8 @@ pub struct Controller {
     pub is_power_enabled: Option<bool>,
     /// key 为 effect id
     pub effects: Option<HashMap<String, Effect>>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 /// Spawn 对象的 Spawning 字段
@@ -373,12 +501,6 @@ pub struct Spawning {
     pub remaining_time: u64,
 }
 
-/// Spawn 对象的 Store 字段
-#[derive(Serialize, Deserialize, Debug)]
-pub struct SpawnStore {
-    pub energy: Option<i32>,
-}
-
 /// Spawn 对象
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Spawn {
@@ -392,9 +514,11 @@ pub struct Spawn {
     pub notify_when_attacked: bool,
     pub spawning: Option<Spawning>,
     pub off: bool,
-    pub store: SpawnStore,
+    pub store: Store,
     #[serde(rename = "storeCapacityResource")]
-    pub store_capacity_resource: SpawnStore,
+    pub store_capacity_resource: Store,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 /// Extension 对象
@@ -408,15 +532,14 @@ pub struct Extension {
     pub user: String,
     #[serde(rename = "notifyWhenAttacked")]
     pub notify_when_attacked: bool,
-    pub store: SpawnStore,
+    pub store: Store,
     #[serde(rename = "storeCapacityResource")]
-    pub store_capacity_resource: SpawnStore,
+    pub store_capacity_resource: Store,
     pub off: bool,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
-/// Storage 对象的 Store 字段
-pub type Store = HashMap<String, Option<i32>>;
-
 /// Storage 对象
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Storage {
@@ -432,6 +555,8 @@ pub struct Storage {
     #[serde(rename = "storeCapacity")]
     pub store_capacity: Option<i32>,
     pub effects: Option<HashMap<String, Effect>>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 /// Tower 对象的 ActionLog 字段
@@ -460,11 +585,13 @@ pub struct Tower {
     #[serde(rename = "notifyWhenAttacked")]
     pub notify_when_attacked: bool,
     pub user: String,
-    pub store: SpawnStore,
+    pub store: Store,
     #[serde(rename = "storeCapacityResource")]
-    pub store_capacity_resource: SpawnStore,
+    pub store_capacity_resource: Store,
     #[serde(rename = "actionLog")]
     pub action_log: ActionLog,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 /// Rampart 对象
@@ -480,6 +607,8 @@ pub struct Rampart {
     pub notify_when_attacked: bool,
     #[serde(rename = "nextDecayTime")]
     pub next_decay_time: Option<u64>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 /// Extractor 对象
@@ -494,6 +623,8 @@ pub struct Extractor {
     #[serde(rename = "notifyWhenAttacked")]
     pub notify_when_attacked: bool,
     pub cooldown: Option<i32>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 /// Terminal 对象
@@ -513,6 +644,8 @@ pub struct Terminal {
     #[serde(rename = "cooldownTime")]
     pub cooldown_time: Option<u64>,
     pub send: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 /// Observer 对象
@@ -528,6 +661,8 @@ pub struct Observer {
     pub notify_when_attacked: bool,
     #[serde(rename = "observeRoom")]
     pub observe_room: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 /// PowerSpawn 对象
@@ -541,9 +676,11 @@ pub struct PowerSpawn {
     pub user: String,
     #[serde(rename = "notifyWhenAttacked")]
     pub notify_when_attacked: bool,
-    pub store: HashMap<String, Option<i32>>, // 包含 energy 和 power
+    pub store: Store, // 包含 energy 和 power
     #[serde(rename = "storeCapacityResource")]
-    pub store_capacity_resource: HashMap<String, Option<i32>>, // 包含 energy 和 power
+    pub store_capacity_resource: Store, // 包含 energy 和 power
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 /// Nuker 对象
@@ -557,11 +694,13 @@ pub struct Nuker {
     pub user: String,
     #[serde(rename = "notifyWhenAttacked")]
     pub notify_when_attacked: bool,
-    pub store: HashMap<String, Option<i32>>, // 包含 energy 和 G
+    pub store: Store, // 包含 energy 和 G
     #[serde(rename = "storeCapacityResource")]
-    pub store_capacity_resource: HashMap<String, Option<i32>>, // 包含 energy 和 G
+    pub store_capacity_resource: Store, // 包含 energy 和 G
     #[serde(rename = "cooldownTime")]
     pub cooldown_time: Option<u64>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 /// Factory 对象
@@ -583,6 +722,8 @@ pub struct Factory {
     pub cooldown_time: Option<u64>,
     pub effects: Option<HashMap<String, Effect>>,
     pub level: Option<i32>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 /// Lab 对象
@@ -603,19 +744,12 @@ pub struct Lab {
     #[serde(rename = "storeCapacity")]
     pub store_capacity: Option<i32>,
     #[serde(rename = "storeCapacityResource")]
-    pub store_capacity_resource: SpawnStore,
+    pub store_capacity_resource: Store,
     #[serde(rename = "mineralAmount")]
     pub mineral_amount: Option<i32>,
     pub effects: Option<HashMap<String, Effect>>,
-}
-
-/// Creep 对象的 Body 字段
-#[derive(Serialize, Deserialize, Debug)]
-pub struct CreepBodyPart {
-    #[serde(rename = "type")]
-    pub part_type: String,
-    pub hits: Option<i32>,
-    pub boost: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 /// Creep 对象
@@ -636,6 +770,11 @@ pub struct Creep {
     pub store_capacity: Option<i32>,
     #[serde(rename = "notifyWhenAttacked")]
     pub notify_when_attacked: Option<bool>,
+    /// creep 死亡的游戏时间
+    #[serde(rename = "ageTime")]
+    pub age_time: Option<u64>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 /// PowerCreep 对象
@@ -659,6 +798,8 @@ pub struct PowerCreep {
     #[serde(rename = "className")]
     pub class_name: String,
     pub power: Option<HashMap<i32, PowerInfo>>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 /// PowerCreep 对象的 Power 字段
@@ -668,3 +809,638 @@ pub struct PowerInfo {
     #[serde(rename = "cooldownTime")]
     pub cooldown_time: Option<u64>,
 }
+
+/// Link 对象的 ActionLog 字段
+#[derive(Serialize, Deserialize, Debug)]
+pub struct LinkActionLog {
+    #[serde(rename = "transferEnergy")]
+    pub transfer_energy: Option<Point>,
+}
+
+/// Link 对象
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Link {
+    #[serde(flatten)]
+    pub base_object: BaseObject,
+    pub hits: i32,
+    #[serde(rename = "hitsMax")]
+    pub hits_max: i32,
+    pub user: String,
+    #[serde(rename = "notifyWhenAttacked")]
+    pub notify_when_attacked: bool,
+    pub store: Store,
+    #[serde(rename = "storeCapacityResource")]
+    pub store_capacity_resource: Store,
+    pub cooldown: Option<i32>,
+    #[serde(rename = "actionLog")]
+    pub action_log: Option<LinkActionLog>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Container 对象
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Container {
+    #[serde(flatten)]
+    pub base_object: BaseObject,
+    pub hits: i32,
+    #[serde(rename = "hitsMax")]
+    pub hits_max: i32,
+    #[serde(rename = "notifyWhenAttacked")]
+    pub notify_when_attacked: Option<bool>,
+    pub store: Store,
+    #[serde(rename = "storeCapacity")]
+    pub store_capacity: Option<i32>,
+    #[serde(rename = "nextDecayTime")]
+    pub next_decay_time: Option<u64>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Portal 对象的 Destination 字段，跨 shard 的传送门没有坐标
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PortalDestination {
+    pub x: Option<i32>,
+    pub y: Option<i32>,
+    pub room: String,
+    pub shard: Option<String>,
+}
+
+/// Portal 对象
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Portal {
+    #[serde(flatten)]
+    pub base_object: BaseObject,
+    pub destination: PortalDestination,
+    /// 传送门消失的时间戳，单位 ms，永久传送门没有该字段
+    #[serde(rename = "unstableDate")]
+    pub unstable_date: Option<u64>,
+    #[serde(rename = "decayTime")]
+    pub decay_time: Option<u64>,
+    pub disabled: Option<bool>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// KeeperLair 对象
+#[derive(Serialize, Deserialize, Debug)]
+pub struct KeeperLair {
+    #[serde(flatten)]
+    pub base_object: BaseObject,
+    #[serde(rename = "nextSpawnTime")]
+    pub next_spawn_time: Option<u64>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// InvaderCore 对象
+#[derive(Serialize, Deserialize, Debug)]
+pub struct InvaderCore {
+    #[serde(flatten)]
+    pub base_object: BaseObject,
+    pub hits: Option<i32>,
+    #[serde(rename = "hitsMax")]
+    pub hits_max: Option<i32>,
+    pub user: Option<String>,
+    pub level: Option<i32>,
+    #[serde(rename = "templateName")]
+    pub template_name: Option<String>,
+    #[serde(rename = "strongholdId")]
+    pub stronghold_id: Option<String>,
+    #[serde(rename = "deployTime")]
+    pub deploy_time: Option<u64>,
+    #[serde(rename = "nextExpandTime")]
+    pub next_expand_time: Option<u64>,
+    #[serde(rename = "decayTime")]
+    pub decay_time: Option<u64>,
+    pub effects: Option<HashMap<String, Effect>>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Ruin 对象的 Structure 字段，即被摧毁的建筑
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RuinStructure {
+    pub id: String,
+    pub r#type: String,
+    pub hits: Option<i32>,
+    #[serde(rename = "hitsMax")]
+    pub hits_max: Option<i32>,
+    pub user: Option<String>,
+}
+
+/// Ruin 对象
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Ruin {
+    #[serde(flatten)]
+    pub base_object: BaseObject,
+    pub user: Option<String>,
+    pub store: Store,
+    #[serde(rename = "destroyTime")]
+    pub destroy_time: Option<u64>,
+    #[serde(rename = "decayTime")]
+    pub decay_time: Option<u64>,
+    pub structure: Option<RuinStructure>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Tombstone 对象
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Tombstone {
+    #[serde(flatten)]
+    pub base_object: BaseObject,
+    pub user: Option<String>,
+    pub store: Store,
+    #[serde(rename = "deathTime")]
+    pub death_time: Option<u64>,
+    #[serde(rename = "decayTime")]
+    pub decay_time: Option<u64>,
+    #[serde(rename = "creepId")]
+    pub creep_id: Option<String>,
+    #[serde(rename = "creepName")]
+    pub creep_name: Option<String>,
+    #[serde(rename = "creepTicksToLive")]
+    pub creep_ticks_to_live: Option<i32>,
+    /// 死亡 creep 的身体部件类型
+    #[serde(rename = "creepBody")]
+    pub creep_body: Option<Vec<BodyPartType>>,
+    #[serde(rename = "creepSaying")]
+    pub creep_saying: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Deposit 对象
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Deposit {
+    #[serde(flatten)]
+    pub base_object: BaseObject,
+    /// 沉积物类型，silicon | metal | biomass | mist
+    #[serde(rename = "depositType")]
+    pub deposit_type: String,
+    /// 已经采集的总量，决定采集冷却时间
+    pub harvested: Option<i32>,
+    #[serde(rename = "cooldownTime")]
+    pub cooldown_time: Option<u64>,
+    #[serde(rename = "decayTime")]
+    pub decay_time: Option<u64>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// PowerBank 对象
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PowerBank {
+    #[serde(flatten)]
+    pub base_object: BaseObject,
+    pub hits: i32,
+    #[serde(rename = "hitsMax")]
+    pub hits_max: i32,
+    /// 新版本服务器中 power 存放在 store 中
+    pub store: Option<Store>,
+    pub power: Option<i32>,
+    #[serde(rename = "decayTime")]
+    pub decay_time: Option<u64>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// ConstructionSite 对象
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ConstructionSite {
+    #[serde(flatten)]
+    pub base_object: BaseObject,
+    pub user: String,
+    #[serde(rename = "structureType")]
+    pub structure_type: String,
+    /// 建造 spawn 时的名称
+    pub name: Option<String>,
+    pub progress: i32,
+    #[serde(rename = "progressTotal")]
+    pub progress_total: i32,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Nuke 对象
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Nuke {
+    #[serde(flatten)]
+    pub base_object: BaseObject,
+    #[serde(rename = "launchRoomName")]
+    pub launch_room_name: String,
+    #[serde(rename = "landTime")]
+    pub land_time: u64,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Flag 对象
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Flag {
+    #[serde(flatten)]
+    pub base_object: BaseObject,
+    pub name: Option<String>,
+    pub user: Option<String>,
+    pub color: Option<i32>,
+    #[serde(rename = "secondaryColor")]
+    pub secondary_color: Option<i32>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// 代码模块，文本模块为 js 代码，二进制模块（如 wasm）以 base64 编码
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum CodeModule {
+    /// js 文本模块
+    Text(String),
+    /// 二进制模块
+    Binary {
+        /// base64 编码后的二进制数据
+        binary: String,
+    },
+}
+
+impl CodeModule {
+    /// 根据原始二进制数据构造二进制模块
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        Self::Binary {
+            binary: BASE64_STANDARD.encode(bytes),
+        }
+    }
+
+    /// 是否为二进制模块
+    pub fn is_binary(&self) -> bool {
+        matches!(self, Self::Binary { .. })
+    }
+
+    /// 获取模块的原始数据，二进制模块会进行 base64 解码
+    pub fn to_bytes(&self) -> Result<Vec<u8>, base64::DecodeError> {
+        match self {
+            Self::Text(text) => Ok(text.as_bytes().to_vec()),
+            Self::Binary { binary } => BASE64_STANDARD.decode(binary),
+        }
+    }
+}
+
+/// 代码分支
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Branch {
+    pub _id: String,
+    pub branch: String,
+    /// 是否为世界服务器的激活分支
+    #[serde(rename = "activeWorld")]
+    pub active_world: Option<bool>,
+    /// 是否为模拟器的激活分支
+    #[serde(rename = "activeSim")]
+    pub active_sim: Option<bool>,
+}
+
+/// 激活分支的位置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActiveBranch {
+    /// 世界服务器
+    World,
+    /// 模拟器
+    Sim,
+}
+
+impl ActiveBranch {
+    /// 接口使用的名称
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::World => "activeWorld",
+            Self::Sim => "activeSim",
+        }
+    }
+}
+
+/// 控制台命令的执行结果，命令输出需要通过 websocket 的 console 频道获取
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ConsoleResult {
+    /// 1 表示命令已被服务器接收
+    pub ok: Option<i32>,
+    /// 写入的命令数量
+    pub n: Option<i32>,
+}
+
+/// 市场订单类型
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum MarketOrderType {
+    Buy,
+    Sell,
+}
+
+/// 市场订单索引，每种资源一条
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MarketOrderIndex {
+    /// 资源类型
+    pub _id: String,
+    /// 订单数量
+    pub count: i32,
+    #[serde(rename = "avgPrice")]
+    pub avg_price: Option<f64>,
+    #[serde(rename = "stddevPrice")]
+    pub stddev_price: Option<f64>,
+}
+
+/// 市场订单
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MarketOrder {
+    pub _id: String,
+    /// 订单类型
+    pub r#type: MarketOrderType,
+    /// 资源类型
+    #[serde(rename = "resourceType")]
+    pub resource_type: String,
+    /// 单价
+    pub price: f64,
+    /// 当前可交易数量
+    pub amount: i64,
+    /// 剩余数量
+    #[serde(rename = "remainingAmount")]
+    pub remaining_amount: i64,
+    /// 订单总数量，只有自己的订单才有
+    #[serde(rename = "totalAmount")]
+    pub total_amount: Option<i64>,
+    /// 订单所在房间，power creep 等无房间订单为空
+    #[serde(rename = "roomName")]
+    pub room_name: Option<String>,
+    /// 创建订单的 tick
+    pub created: Option<u64>,
+    /// 创建订单的时间戳
+    #[serde(rename = "createdTimestamp")]
+    pub created_timestamp: Option<u64>,
+    /// 订单所属用户 id，只有自己的订单才有
+    pub user: Option<String>,
+    /// 订单是否激活
+    pub active: Option<bool>,
+}
+
+/// 市场每日统计
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MarketStat {
+    #[serde(rename = "resourceType")]
+    pub resource_type: String,
+    /// 日期，格式 `YYYY-MM-DD`
+    pub date: String,
+    /// 成交笔数
+    pub transactions: i64,
+    /// 成交量
+    pub volume: i64,
+    #[serde(rename = "avgPrice")]
+    pub avg_price: f64,
+    #[serde(rename = "stddevPrice")]
+    pub stddev_price: f64,
+}
+
+/// credits 变动记录
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MoneyHistoryEntry {
+    pub _id: String,
+    /// 时间，ISO 8601 格式
+    pub date: String,
+    /// 变动发生的 tick
+    pub tick: u64,
+    /// 用户 id
+    pub user: String,
+    /// 变动类型，如 "market.buy" | "market.sell" | "market.fee"
+    pub r#type: String,
+    /// 变动后的余额
+    pub balance: f64,
+    /// 变动量
+    pub change: f64,
+    /// 市场交易详情，结构随变动类型不同
+    pub market: Option<serde_json::Value>,
+}
+
+/// 地图统计中的单个房间信息
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MapRoomStat {
+    /// "normal" | "out of borders"
+    pub status: Option<String>,
+    /// 新手区结束时间
+    pub novice: Option<u64>,
+    /// 重生区结束时间
+    #[serde(rename = "respawnArea")]
+    pub respawn_area: Option<u64>,
+    /// 房间开放时间
+    #[serde(rename = "openTime")]
+    pub open_time: Option<u64>,
+    /// 房间所有者
+    pub own: Option<MapRoomOwner>,
+    pub sign: Option<Sign>,
+    /// 官方签名，如新手区提示
+    #[serde(rename = "hardSign")]
+    pub hard_sign: Option<HardSign>,
+    /// 是否处于安全模式
+    #[serde(rename = "safeMode")]
+    pub safe_mode: Option<bool>,
+    /// 房间矿物
+    #[serde(rename = "minerals0")]
+    pub mineral: Option<MapRoomMineral>,
+    /// 与 statName 对应的其他统计数据
+    #[serde(flatten)]
+    pub stats: HashMap<String, serde_json::Value>,
+}
+
+/// 官方签名
+#[derive(Serialize, Deserialize, Debug)]
+pub struct HardSign {
+    pub text: String,
+    pub time: Option<i32>,
+    #[serde(rename = "datetime")]
+    pub date_time: Option<u64>,
+    #[serde(rename = "endDatetime")]
+    pub end_date_time: Option<u64>,
+}
+
+/// 地图统计中的房间所有者
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MapRoomOwner {
+    /// 用户 id，可以在 `MapStatsData.users` 中查到用户信息
+    pub user: String,
+    /// 控制器等级，预定房间为 0
+    pub level: i32,
+}
+
+/// 地图统计中的房间矿物
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MapRoomMineral {
+    /// 矿物类型
+    pub r#type: String,
+    /// 矿物密度
+    pub density: i32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resource::ResourceType;
+
+    #[test]
+    fn test_code_module_serde() {
+        let modules: HashMap<String, CodeModule> = serde_json::from_str(
+            r#"{"main": "module.exports.loop = function() {}", "bot": {"binary": "AGFzbQ=="}}"#,
+        )
+        .unwrap();
+        assert!(!modules["main"].is_binary());
+        assert_eq!(modules["bot"], CodeModule::from_bytes(b"\0asm"));
+        assert_eq!(modules["bot"].to_bytes().unwrap(), b"\0asm");
+    }
+
+    #[test]
+    fn test_more_room_objects() {
+        let objects: Vec<RoomObject> = serde_json::from_value(serde_json::json!([
+            { "_id": "1", "type": "link", "room": "E1N1", "x": 1, "y": 1, "hits": 1000, "hitsMax": 1000,
+              "user": "u", "notifyWhenAttacked": true, "store": { "energy": 400 },
+              "storeCapacityResource": { "energy": 800 }, "cooldown": 0,
+              "actionLog": { "transferEnergy": { "x": 2, "y": 2, "roomName": "E1N1" } } },
+            { "_id": "2", "type": "container", "room": "E1N1", "x": 1, "y": 2, "hits": 250000,
+              "hitsMax": 250000, "notifyWhenAttacked": true, "store": { "energy": 1500, "H": 500 },
+              "storeCapacity": 2000, "nextDecayTime": 100 },
+            { "_id": "3", "type": "portal", "room": "E10N10", "x": 25, "y": 25,
+              "destination": { "shard": "shard2", "room": "E10N10" } },
+            { "_id": "4", "type": "keeperLair", "room": "E4N4", "x": 10, "y": 10, "nextSpawnTime": 300 },
+            { "_id": "5", "type": "invaderCore", "room": "E5N5", "x": 25, "y": 25, "hits": 100000,
+              "hitsMax": 100000, "user": "2", "level": 3, "templateName": "bunker3",
+              "strongholdId": "s1", "deployTime": 10 },
+            { "_id": "6", "type": "ruin", "room": "E1N1", "x": 3, "y": 3, "user": "u",
+              "store": { "energy": 100 }, "destroyTime": 1, "decayTime": 500,
+              "structure": { "id": "s", "type": "extension", "hits": 0, "hitsMax": 1000, "user": "u" } },
+            { "_id": "7", "type": "tombstone", "room": "E1N1", "x": 4, "y": 4, "user": "u",
+              "store": { "energy": 50, "XUH2O": 30 }, "deathTime": 1, "decayTime": 50,
+              "creepId": "c", "creepName": "harvester", "creepTicksToLive": 0,
+              "creepBody": ["work", "carry", "move"], "creepSaying": null },
+            { "_id": "8", "type": "deposit", "room": "E10N1", "x": 5, "y": 5, "depositType": "mist",
+              "harvested": 120, "cooldownTime": 10, "decayTime": 5000 },
+            { "_id": "9", "type": "powerBank", "room": "E20N1", "x": 6, "y": 6, "hits": 2000000,
+              "hitsMax": 2000000, "store": { "power": 3000 }, "decayTime": 5000 },
+            { "_id": "10", "type": "constructionSite", "room": "E1N1", "x": 7, "y": 7, "user": "u",
+              "structureType": "spawn", "name": "Spawn2", "progress": 100, "progressTotal": 15000 },
+            { "_id": "11", "type": "nuke", "room": "E1N1", "x": 8, "y": 8,
+              "launchRoomName": "E3N3", "landTime": 50000 },
+            { "_id": "12", "type": "flag", "room": "E1N1", "x": 9, "y": 9, "name": "Flag1",
+              "color": 1, "secondaryColor": 2 }
+        ]))
+        .unwrap();
+
+        let names: Vec<_> = objects
+            .iter()
+            .map(|object| match object {
+                RoomObject::Link(link) => {
+                    assert_eq!(link.store.get(ResourceType::Energy), 400);
+                    "link"
+                }
+                RoomObject::Container(container) => {
+                    assert_eq!(container.store.get(ResourceType::Hydrogen), 500);
+                    "container"
+                }
+                RoomObject::Portal(portal) => {
+                    assert_eq!(portal.destination.shard.as_deref(), Some("shard2"));
+                    "portal"
+                }
+                RoomObject::KeeperLair(_) => "keeperLair",
+                RoomObject::InvaderCore(core) => {
+                    assert_eq!(core.level, Some(3));
+                    "invaderCore"
+                }
+                RoomObject::Ruin(ruin) => {
+                    assert_eq!(ruin.structure.as_ref().unwrap().r#type, "extension");
+                    "ruin"
+                }
+                RoomObject::Tombstone(tombstone) => {
+                    assert_eq!(tombstone.store.get(ResourceType::CatalyzedUtriumAcid), 30);
+                    "tombstone"
+                }
+                RoomObject::Deposit(deposit) => {
+                    assert_eq!(deposit.deposit_type, "mist");
+                    "deposit"
+                }
+                RoomObject::PowerBank(_) => "powerBank",
+                RoomObject::ConstructionSite(site) => {
+                    assert_eq!(site.structure_type, "spawn");
+                    "constructionSite"
+                }
+                RoomObject::Nuke(nuke) => {
+                    assert_eq!(nuke.land_time, 50000);
+                    "nuke"
+                }
+                RoomObject::Flag(_) => "flag",
+                _ => "other",
+            })
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "link",
+                "container",
+                "portal",
+                "keeperLair",
+                "invaderCore",
+                "ruin",
+                "tombstone",
+                "deposit",
+                "powerBank",
+                "constructionSite",
+                "nuke",
+                "flag"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unknown_objects_and_extra_fields() {
+        let raw = serde_json::json!([
+            { "_id": "1", "type": "source", "room": "E1N1", "x": 1, "y": 1, "energy": 3000,
+              "energyCapacity": 3000, "ticksToRegeneration": 300, "invaderHarvested": 0,
+              "nextRegenerationTime": null, "newField": { "a": 1 } },
+            { "_id": "2", "type": "scoreCollector", "room": "E1N1", "x": 2, "y": 2, "score": 42 }
+        ]);
+        let objects: Vec<RoomObject> = serde_json::from_value(raw.clone()).unwrap();
+        match &objects[0] {
+            RoomObject::Source(source) => {
+                assert_eq!(source.extra.len(), 1);
+                assert_eq!(source.extra["newField"]["a"], 1);
+            }
+            object => panic!("unexpected object {:?}", object),
+        }
+        match &objects[1] {
+            RoomObject::Unknown(unknown) => {
+                assert_eq!(unknown.object_type, "scoreCollector");
+                assert_eq!(unknown.data["score"], 42);
+                assert_eq!(unknown.data["_id"], "2");
+            }
+            object => panic!("unexpected object {:?}", object),
+        }
+        // 重新序列化后数据不变
+        assert_eq!(serde_json::to_value(&objects).unwrap(), raw);
+    }
+
+    #[test]
+    fn test_known_object_missing_field() {
+        // 已知类型缺少必需字段时返回错误，不会当作未知对象
+        let tower = serde_json::json!({
+            "_id": "t1", "type": "tower", "room": "E1N1", "x": 1, "y": 1, "hits": 3000,
+            "hitsMax": 3000, "notifyWhenAttacked": true, "user": "u1", "store": { "energy": 1000 }
+        });
+        let error = serde_json::from_value::<RoomObject>(tower).unwrap_err();
+        assert!(
+            error.to_string().contains("storeCapacityResource"),
+            "{}",
+            error
+        );
+        for object_type in ROOM_OBJECT_TYPES {
+            let object = serde_json::json!({ "_id": "1", "type": object_type, "room": "E1N1" });
+            assert!(
+                serde_json::from_value::<RoomObject>(object).is_err(),
+                "{}",
+                object_type
+            );
+        }
+        assert!(serde_json::from_value::<RoomObject>(serde_json::json!({ "_id": "1" })).is_err());
+    }
+}
@@ -1,3 +1,55 @@
+use std::io::Read;
+
+use base64::{Engine, prelude::BASE64_STANDARD};
+use flate2::read::{GzDecoder, ZlibDecoder};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::error::{ScreepsError, ScreepsResult};
+
+/// gz 压缩数据的前缀
+pub const GZ_PREFIX: &str = "gz:";
+
+/// 解码 `gz:` 前缀的 base64 + gzip 压缩数据，没有前缀时原样返回
+pub fn decode_gz_data(data: &str) -> ScreepsResult<String> {
+    let Some(encoded) = data.strip_prefix(GZ_PREFIX) else {
+        return Ok(data.to_string());
+    };
+    let compressed = BASE64_STANDARD
+        .decode(encoded)
+        .map_err(|e| ScreepsError::Decode(e.to_string()))?;
+    let mut decoded = String::new();
+    GzDecoder::new(compressed.as_slice())
+        .read_to_string(&mut decoded)
+        .map_err(|e| ScreepsError::Decode(e.to_string()))?;
+    Ok(decoded)
+}
+
+/// 解码 `gz:` 前缀的 base64 + zlib 压缩数据，websocket 推送的数据使用这种格式，没有前缀时原样返回
+pub fn decode_zlib_data(data: &str) -> ScreepsResult<String> {
+    let Some(encoded) = data.strip_prefix(GZ_PREFIX) else {
+        return Ok(data.to_string());
+    };
+    let compressed = BASE64_STANDARD
+        .decode(encoded)
+        .map_err(|e| ScreepsError::Decode(e.to_string()))?;
+    let mut decoded = String::new();
+    ZlibDecoder::new(compressed.as_slice())
+        .read_to_string(&mut decoded)
+        .map_err(|e| ScreepsError::Decode(e.to_string()))?;
+    Ok(decoded)
+}
+
+/// 将接口返回的值解码为指定类型，`gz:` 前缀的字符串会先解压再按 json 解析
+pub fn decode_gz_value<T: DeserializeOwned>(value: Value) -> ScreepsResult<T> {
+    match value {
+        Value::String(data) if data.starts_with(GZ_PREFIX) => {
+            Ok(serde_json::from_str(&decode_gz_data(&data)?)?)
+        }
+        value => Ok(serde_json::from_value(value)?),
+    }
+}
+
 /// 创建一个宏来简化 ScreepsApi 的实例化
 #[macro_export]
 macro_rules! screeps_api_from_env {
@@ -39,3 +91,52 @@ macro_rules! screeps_api_from_env {
         }
     }};
 }
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::{Compression, write::GzEncoder};
+    use serde_json::json;
+
+    use super::*;
+
+    fn encode(data: &str) -> String {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data.as_bytes()).unwrap();
+        format!(
+            "{}{}",
+            GZ_PREFIX,
+            BASE64_STANDARD.encode(encoder.finish().unwrap())
+        )
+    }
+
+    #[test]
+    fn test_decode_gz_data() {
+        assert_eq!(decode_gz_data(&encode("{\"a\":1}")).unwrap(), "{\"a\":1}");
+        assert_eq!(decode_gz_data("plain").unwrap(), "plain");
+        assert!(decode_gz_data("gz:!!!").is_err());
+    }
+
+    #[test]
+    fn test_decode_zlib_data() {
+        // 服务器使用 zlib.deflate 压缩的 websocket 数据
+        let data = "gz:eJyLVirKz8+1Ks5ILEox1nc1NA42NFbSqVbKT8pKTS4pVrKqVko0BJEZmSCeoYFBba2OUnpibmpIZm4qUMDIuDYWAPlHFYQ=";
+        assert_eq!(
+            decode_zlib_data(data).unwrap(),
+            r#"["room:shard3/E13S13",{"objects":{"a1":{"hits":100}},"gameTime":123}]"#
+        );
+        assert_eq!(decode_zlib_data("plain").unwrap(), "plain");
+        assert!(decode_zlib_data(&encode("{}")).is_err());
+    }
+
+    #[test]
+    fn test_decode_gz_value() {
+        let value: Value =
+            decode_gz_value(Value::String(encode("{\"rooms\":[\"E1N1\"]}"))).unwrap();
+        assert_eq!(value, json!({ "rooms": ["E1N1"] }));
+
+        let rooms: Vec<String> = decode_gz_value(json!(["E1N1"])).unwrap();
+        assert_eq!(rooms, vec!["E1N1".to_string()]);
+    }
+}